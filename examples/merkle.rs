@@ -0,0 +1,94 @@
+use std::env::args;
+use std::fs::read_to_string;
+
+use hope_contract::merkle::{hash_pair, leaf_hash, Hash};
+
+// Builds the merkle root and proofs for an allowlist phase.
+//
+// Usage: cargo run --example merkle -- allowlist.csv
+//
+// Every line of the csv holds an address, optionally followed by its
+// allocation: `juno1...` or `juno1...,3`. Empty lines and lines starting
+// with `#` are skipped. Leaves hash the line as the contract does, the
+// address alone or `address,allocation`.
+fn main() {
+    let path = args()
+        .nth(1)
+        .expect("usage: cargo run --example merkle -- <allowlist.csv>");
+    let csv = read_to_string(&path).unwrap();
+
+    let entries: Vec<(String, Option<String>)> = csv
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let address = fields.next().unwrap().to_string();
+            let allocation = fields.next().map(str::to_string);
+            (address, allocation)
+        })
+        .collect();
+    assert!(!entries.is_empty(), "allowlist is empty");
+
+    let leaves: Vec<Hash> = entries
+        .iter()
+        .map(|(address, allocation)| leaf_hash(address, allocation.as_deref()))
+        .collect();
+    let layers = build_layers(leaves);
+    let root = layers.last().unwrap()[0];
+
+    println!("{{");
+    println!("  \"root\": \"{}\",", hex::encode(root));
+    println!("  \"proofs\": [");
+    for (i, (address, allocation)) in entries.iter().enumerate() {
+        let proof: Vec<String> = build_proof(&layers, i)
+            .iter()
+            .map(|hash| format!("\"{}\"", hex::encode(hash)))
+            .collect();
+        let allocation = match allocation {
+            Some(allocation) => format!("\"{}\"", allocation),
+            None => "null".to_string(),
+        };
+        let separator = if i + 1 < entries.len() { "," } else { "" };
+        println!(
+            "    {{ \"address\": \"{}\", \"allocation\": {}, \"proof\": [{}] }}{}",
+            address,
+            allocation,
+            proof.join(", "),
+            separator
+        );
+    }
+    println!("  ]");
+    println!("}}");
+}
+
+// each layer pairs up the previous one, an odd hash is carried up as is
+fn build_layers(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut layers = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let next = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        layers.push(next);
+    }
+    layers
+}
+
+fn build_proof(layers: &[Vec<Hash>], mut index: usize) -> Vec<Hash> {
+    let mut proof = vec![];
+    for layer in &layers[..layers.len() - 1] {
+        let sibling = index ^ 1;
+        if sibling < layer.len() {
+            proof.push(layer[sibling]);
+        }
+        index /= 2;
+    }
+    proof
+}
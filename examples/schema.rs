@@ -2,8 +2,8 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use Hope_Contract::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use Hope_Contract::state::State;
+use hope_contract::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use hope_contract::state::State;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -14,5 +14,6 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
 }
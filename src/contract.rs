@@ -1,32 +1,319 @@
+use std::convert::TryFrom;
+
 use cosmwasm_std::{
-    entry_point, from_binary, to_binary,   CosmosMsg, Deps, DepsMut,Binary,Decimal,
-    Env, MessageInfo, QueryRequest, Response, StdResult, Uint128, WasmMsg, WasmQuery, 
+    entry_point, from_binary, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Event, Fraction, MessageInfo, QueryRequest, Reply, Response, StdError, StdResult,
+    Storage, SubMsg, Timestamp, Uint128, Uint256, WasmMsg, WasmQuery,
 };
+use cw2::{set_contract_version, CONTRACT};
+use cw_utils::{parse_reply_instantiate_data, Expiration};
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, HopeMintMsg, InstantiateMsg, QueryMsg};
+use crate::merkle::{decode_hash, leaf_hash, verify_proof, Hash};
+use crate::migrations::{
+    migrate_legacy_state, migrate_mint_ledger_indexes, migrate_storage_layout,
+    migrate_token_prefixes, migrate_unique_minters,
+};
+use crate::msg::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, HopeMintMsg, InstantiateMsg, MigrateMsg,
+    MintStatusResponse, NftContract, OwnershipResponse, QueryMsg, RoleInfo,
+    SimulateBuyTokenResponse, SimulateMintResponse, StateResponse, TokenContract, TokenDetail,
+    TokensDetailedResponse, TreasuryResponse, WalletStatus,
+};
 use crate::state::{
-    config, config_read, read_maximum_nft, read_nft_address, read_token_address, read_token_count,
-    read_user_info, read_users, store_maximum_nft, store_nft_address, store_token_address,
-    store_token_count, store_users, Metadata, State, TokenCount, UserInfo,
+    clear_roles, next_mint_sequence, read_all_payment_tokens, read_all_roles, read_collection,
+    read_collection_counter, read_collection_mints, read_collections, read_exchange, read_issued,
+    read_max_per_tx, read_maximum_nft, read_mint_history, read_mint_record, read_native_price,
+    read_native_prices, read_nft_address, read_payment_token, read_payment_tokens,
+    read_payout_address, read_pending_owner, read_phase_mints, read_phases, read_redemption,
+    read_redemption_window, read_roles, read_token_address, read_token_count, read_token_prefix,
+    read_top_minters, read_total_issued, read_unique_minters, read_user_info, read_users,
+    read_users_info, remove_collection, remove_native_price, remove_payment_token,
+    remove_pending_owner, store_collection, store_collection_mints, store_exchange, store_issued,
+    store_max_per_tx, store_maximum_nft, store_mint_record, store_native_price, store_nft_address,
+    store_payment_token, store_payout_address, store_pending_mint, store_pending_owner,
+    store_pending_token_price, store_phase_mints, store_phases, store_redemption,
+    store_redemption_window, store_roles, store_token_address, store_token_count,
+    store_token_prefix, store_total_issued, store_users, take_pending_mint,
+    take_pending_token_price, Asset, AssetInfo, Collection, Eligibility, ExchangeConfig, Metadata,
+    MintRecord, PaymentToken, PendingMint, PendingOwner, Phase, RedemptionConfig, RedemptionWindow,
+    Role, State, TokenCount, UserInfo, CONFIG, TOKEN_ADDRESS,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, MinterResponse};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw721::{AllNftInfoResponse, ContractInfoResponse, TokensResponse};
+use cw721_base::{
+    ExecuteMsg as Cw721BaseExecuteMsg, InstantiateMsg as Cw721InstantiateMsg, MintMsg,
+    QueryMsg as Cw721BaseQueryMsg,
 };
-use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
-use cw721::{ContractInfoResponse, TokensResponse};
-use cw721_base::{ExecuteMsg as Cw721BaseExecuteMsg, MintMsg, QueryMsg as Cw721BaseQueryMsg};
+
+const CONTRACT_NAME: &str = "crates.io:hope-mint";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+const INSTANTIATE_NFT_REPLY_ID: u64 = 2;
+// cw721 mints reply with this offset plus a contract wide mint sequence, so the
+// ids never collide with the instantiate replies or across collections
+const MINT_REPLY_ID_OFFSET: u64 = 1_000;
 
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    if msg.max_supply.is_zero() {
+        return Err(invalid_config("max supply must be greater than zero"));
+    }
+    if msg.maximum_nft.is_zero() || msg.maximum_nft > msg.max_supply {
+        return Err(invalid_config(
+            "maximum nft per wallet must be between one and the max supply",
+        ));
+    }
+    let token_price = match &msg.token {
+        TokenContract::Instantiate { mint_price, .. } => *mint_price,
+        TokenContract::Existing { .. } => None,
+    };
+    if msg.payment_tokens.is_empty() && msg.native_prices.is_empty() && token_price.is_none() {
+        return Err(invalid_config("at least one mint price is required"));
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let mut res = Response::new().add_attribute("action", "instantiate");
+    match msg.token {
+        TokenContract::Existing { address } => {
+            let token_address = deps.api.addr_validate(&address)?;
+            store_token_address(deps.storage, &token_address)?;
+            res = res.add_attribute("token_address", token_address);
+        }
+        TokenContract::Instantiate {
+            code_id,
+            label,
+            name,
+            symbol,
+            decimals,
+            initial_balances,
+            cap,
+            mint_price,
+        } => {
+            let init = Cw20InstantiateMsg {
+                name,
+                symbol,
+                decimals,
+                initial_balances,
+                mint: Some(MinterResponse {
+                    minter: env.contract.address.to_string(),
+                    cap,
+                }),
+                marketing: None,
+            };
+            let instantiate = WasmMsg::Instantiate {
+                admin: Some(info.sender.to_string()),
+                code_id,
+                msg: to_binary(&init)?,
+                funds: vec![],
+                label,
+            };
+            res = res.add_submessage(SubMsg::reply_on_success(
+                instantiate,
+                INSTANTIATE_TOKEN_REPLY_ID,
+            ));
+            // the token address is only known in the reply
+            if let Some(mint_price) = mint_price {
+                if mint_price.is_zero() {
+                    return Err(invalid_config("mint price must be greater than zero"));
+                }
+                store_pending_token_price(deps.storage, &mint_price)?;
+            }
+        }
+    }
+    match msg.nft {
+        NftContract::Existing { address } => {
+            let nft_address = deps.api.addr_validate(&address)?;
+            store_nft_address(deps.storage, &nft_address)?;
+            res = res.add_attribute("nft_address", nft_address);
+        }
+        NftContract::Instantiate {
+            code_id,
+            label,
+            name,
+            symbol,
+        } => {
+            let init = Cw721InstantiateMsg {
+                name,
+                symbol,
+                minter: env.contract.address.to_string(),
+            };
+            let instantiate = WasmMsg::Instantiate {
+                admin: Some(info.sender.to_string()),
+                code_id,
+                msg: to_binary(&init)?,
+                funds: vec![],
+                label,
+            };
+            res = res.add_submessage(SubMsg::reply_on_success(
+                instantiate,
+                INSTANTIATE_NFT_REPLY_ID,
+            ));
+        }
+    }
+    store_maximum_nft(deps.storage, &msg.maximum_nft)?;
+
+    for payment_token in msg.payment_tokens {
+        if payment_token.price.is_zero() {
+            return Err(invalid_config("mint price must be greater than zero"));
+        }
+        let token_address = deps.api.addr_validate(&payment_token.address)?;
+        store_payment_token(deps.storage, &token_address, &payment_token.price)?;
+    }
+    for price in msg.native_prices {
+        if price.amount.is_zero() {
+            return Err(invalid_config("mint price must be greater than zero"));
+        }
+        store_native_price(deps.storage, &price.denom, &price.amount)?;
+    }
+    if let Some(payout_address) = msg.payout_address {
+        let payout_address = deps.api.addr_validate(&payout_address)?;
+        store_payout_address(deps.storage, &payout_address)?;
+    }
+
     let state = State {
         total_nft: Uint128::new(0),
-        owner: info.sender.to_string(),
+        owner: Some(info.sender.to_string()),
+        max_supply: msg.max_supply,
+        paused: false,
+        pause_reason: None,
     };
-    config(deps.storage).save(&state)?;
-    Ok(Response::default())
+    CONFIG.save(deps.storage, &state)?;
+    Ok(res)
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let current_version = parse_version(CONTRACT_VERSION)?;
+    let previous = CONTRACT.may_load(deps.storage)?;
+    let previous_version = match &previous {
+        Some(previous) => {
+            if previous.contract != CONTRACT_NAME {
+                return Err(ContractError::CannotMigrate {
+                    previous_contract: previous.contract.clone(),
+                });
+            }
+            parse_version(&previous.version)?
+        }
+        // deployed before the version was recorded
+        None => Version::new(0, 0, 0),
+    };
+    if previous_version > current_version {
+        return Err(ContractError::CannotDowngrade {
+            previous_version: previous_version.to_string(),
+            current_version: current_version.to_string(),
+        });
+    }
+
+    if previous.is_none() {
+        migrate_legacy_state(deps.storage)?;
+    }
+    if previous_version < Version::new(0, 11, 0) {
+        migrate_storage_layout(deps.storage)?;
+    }
+    if previous_version < Version::new(0, 12, 0) {
+        migrate_unique_minters(deps.storage)?;
+    }
+    if previous_version < Version::new(0, 13, 0) {
+        migrate_mint_ledger_indexes(deps.storage)?;
+        migrate_token_prefixes(deps.storage)?;
+    }
+    if previous.is_none() {
+        migrate_legacy_payment_token(deps.storage, msg.legacy_mint_price)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", current_version.to_string()))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+// stores the addresses of the contracts instantiated in factory mode
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_TOKEN_REPLY_ID => {
+            let res = parse_reply_instantiate_data(msg)?;
+            let token_address = deps.api.addr_validate(&res.contract_address)?;
+            store_token_address(deps.storage, &token_address)?;
+            if let Some(mint_price) = take_pending_token_price(deps.storage) {
+                store_payment_token(deps.storage, &token_address, &mint_price)?;
+            }
+            Ok(Response::new().add_attribute("token_address", token_address))
+        }
+        INSTANTIATE_NFT_REPLY_ID => {
+            let res = parse_reply_instantiate_data(msg)?;
+            let nft_address = deps.api.addr_validate(&res.contract_address)?;
+            store_nft_address(deps.storage, &nft_address)?;
+            Ok(Response::new().add_attribute("nft_address", nft_address))
+        }
+        id if id >= MINT_REPLY_ID_OFFSET => reply_mint(deps, env, id),
+        id => Err(ContractError::UnknownReply { id }),
+    }
+}
+
+// records a mint confirmed by the cw721 contract in the mint ledger
+fn reply_mint(deps: DepsMut, env: Env, id: u64) -> Result<Response, ContractError> {
+    let pending = match take_pending_mint(deps.storage, id) {
+        Some(pending) => pending,
+        None => return Err(ContractError::UnknownReply { id }),
+    };
+    let record = MintRecord {
+        token_id: pending.token_id,
+        minter: pending.minter,
+        height: env.block.height,
+        time: Some(env.block.time),
+        payment: pending.payment,
+        phase: pending.phase,
+    };
+    store_mint_record(deps.storage, &record)?;
+    Ok(Response::new().add_event(
+        Event::new("mint_confirmed")
+            .add_attribute("token_id", record.token_id)
+            .add_attribute("minter", record.minter)
+            .add_attribute("height", record.height.to_string()),
+    ))
+}
+
+// The legacy receive hook took its single cw20 token at any amount. Minting
+// through it now needs the token in the payment registry.
+fn migrate_legacy_payment_token(
+    storage: &mut dyn Storage,
+    mint_price: Option<Uint128>,
+) -> Result<(), ContractError> {
+    let token = match TOKEN_ADDRESS.may_load(storage)? {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+    if read_payment_token(storage, &token).is_some() {
+        return Ok(());
+    }
+    match mint_price {
+        Some(price) if !price.is_zero() => Ok(store_payment_token(storage, &token, &price)?),
+        _ => Err(invalid_config(
+            "a mint price is required for the legacy payment token",
+        )),
+    }
+}
+
+fn invalid_config(reason: &str) -> ContractError {
+    ContractError::InvalidConfig {
+        reason: reason.to_string(),
+    }
 }
 
 #[entry_point]
@@ -41,77 +328,500 @@ pub fn execute(
         ExecuteMsg::SetTokenAddress { address } => execute_set_address(deps, info, address),
         ExecuteMsg::SetNftAddress { address } => execute_set_nft_address(deps, info, address),
         ExecuteMsg::BuyToken { amount } => execute_buy_token(deps, info, amount),
+        ExecuteMsg::SetExchange { exchange } => execute_set_exchange(deps, info, exchange),
+        ExecuteMsg::SetRedemption { redemption } => execute_set_redemption(deps, info, redemption),
+        ExecuteMsg::SetCollection {
+            nft_address,
+            token_prefix,
+            max_supply,
+            prices,
+            per_wallet_limit,
+        } => execute_set_collection(
+            deps,
+            info,
+            nft_address,
+            token_prefix,
+            max_supply,
+            prices,
+            per_wallet_limit,
+        ),
+        ExecuteMsg::RemoveCollection { nft_address } => {
+            execute_remove_collection(deps, info, nft_address)
+        }
         ExecuteMsg::SetMaximumNft { amount } => execute_maxium_nft(deps, info, amount),
+        ExecuteMsg::SetMaxPerTx { amount } => execute_set_max_per_tx(deps, info, amount),
+        ExecuteMsg::SetMaxSupply { amount } => execute_set_max_supply(deps, info, amount),
+        ExecuteMsg::SetPhases { phases } => execute_set_phases(deps, info, phases),
+        ExecuteMsg::SetMerkleRoot { phase, root } => {
+            execute_set_merkle_root(deps, info, phase, root)
+        }
+        ExecuteMsg::Pause { reason } => execute_pause(deps, info, reason),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            execute_propose_new_owner(deps, env, info, owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { address, role } => execute_grant_role(deps, info, address, role),
+        ExecuteMsg::RevokeRole { address, role } => execute_revoke_role(deps, info, address, role),
+        ExecuteMsg::Airdrop { recipients, msg } => execute_airdrop(deps, info, recipients, msg),
+        ExecuteMsg::Withdraw { asset, recipient } => execute_withdraw(deps, info, asset, recipient),
+        ExecuteMsg::AddPaymentToken { address, price } => {
+            execute_add_payment_token(deps, info, address, price)
+        }
+        ExecuteMsg::RemovePaymentToken { address } => {
+            execute_remove_payment_token(deps, info, address)
+        }
+        ExecuteMsg::Mint(msg) => execute_mint(deps, env, info, msg),
+        ExecuteMsg::SetNativePrice { denom, price } => {
+            execute_set_native_price(deps, info, denom, price)
+        }
+        ExecuteMsg::RemoveNativePrice { denom } => execute_remove_native_price(deps, info, denom),
     }
 }
 
 fn execute_receive(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
+    cw20_receive_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // a bare HopeMintMsg is still accepted as a mint hook
+    let hook = match from_binary(&cw20_receive_msg.msg) {
+        Ok(hook) => hook,
+        Err(_) => Cw20HookMsg::Mint(from_binary(&cw20_receive_msg.msg)?),
+    };
+    let msg = match hook {
+        Cw20HookMsg::Mint(msg) => msg,
+        Cw20HookMsg::Redeem {} => return execute_redeem(deps, env, info, cw20_receive_msg),
+    };
+
+    // only an accepted payment token may call the receive hook
+    let mint_price = match read_payment_token(deps.storage, &info.sender) {
+        Some(price) => price,
+        None => return Err(ContractError::InvalidToken {}),
+    };
+
+    let minter = deps.api.addr_validate(cw20_receive_msg.sender.as_str())?;
+    let payment = AssetInfo::Token {
+        contract_addr: info.sender.to_string(),
+    };
+
+    execute_mint_nft(
+        deps,
+        env,
+        minter,
+        payment,
+        cw20_receive_msg.amount,
+        mint_price,
+        msg,
+    )
+}
+
+fn execute_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     cw20_receive_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let mut state = config_read(deps.storage).load()?;
+    if CONFIG.load(deps.storage)?.paused {
+        return Err(ContractError::Paused {});
+    }
+    // only the hope token itself can be sold back
+    let token_address = read_token_address(deps.storage)?;
+    if info.sender != token_address {
+        return Err(ContractError::InvalidToken {});
+    }
+    let redeemer = deps.api.addr_validate(&cw20_receive_msg.sender)?;
+    let amount = cw20_receive_msg.amount;
+
+    let redemption = read_redemption(deps.storage)?;
+    let mut window = current_redemption_window(deps.storage, &redemption, env.block.time);
+    window.redeemed = match window.redeemed.checked_add(amount) {
+        Ok(redeemed) if redeemed <= redemption.period_limit => redeemed,
+        _ => return Err(ContractError::RedemptionLimitExceeded {}),
+    };
+
+    // the spread never raises the value, so only the price can overflow
+    let value = amount.full_mul(redemption.price.numerator())
+        / Uint256::from(redemption.price.denominator());
+    let value = Uint128::try_from(value).map_err(StdError::from)?;
+    let payout = value * (Decimal::one() - redemption.spread);
+    if payout.is_zero() {
+        return Err(ContractError::Notenough {});
+    }
+    let reserves = deps
+        .querier
+        .query_balance(&env.contract.address, &redemption.denom)?;
+    if reserves.amount < payout {
+        return Err(ContractError::InsufficientReserves {});
+    }
+    store_redemption_window(deps.storage, &window)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "redeem")
+        .add_attribute("amount", amount)
+        .add_attribute("payout", payout)
+        .add_message(BankMsg::Send {
+            to_address: redeemer.to_string(),
+            amount: vec![Coin {
+                denom: redemption.denom,
+                amount: payout,
+            }],
+        });
+    if redemption.burn {
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: token_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        });
+    }
+    Ok(res)
+}
+
+// starts a fresh window once the configured period has passed
+fn current_redemption_window(
+    storage: &dyn Storage,
+    redemption: &RedemptionConfig,
+    time: Timestamp,
+) -> RedemptionWindow {
+    match read_redemption_window(storage) {
+        Some(window) if time < window.start.plus_seconds(redemption.period) => window,
+        _ => RedemptionWindow {
+            start: time,
+            redeemed: Uint128::zero(),
+        },
+    }
+}
+
+fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: HopeMintMsg,
+) -> Result<Response, ContractError> {
+    // exactly one coin of an accepted denom pays for the mint
+    if info.funds.len() != 1 {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let coin = &info.funds[0];
+    let mint_price = match read_native_price(deps.storage, &coin.denom) {
+        Some(price) => price,
+        None => return Err(ContractError::InvalidFunds {}),
+    };
+    let payment = AssetInfo::NativeToken {
+        denom: coin.denom.clone(),
+    };
+
+    execute_mint_nft(
+        deps,
+        env,
+        info.sender,
+        payment,
+        coin.amount,
+        mint_price,
+        msg,
+    )
+}
+
+// shared by the cw20 and native payment paths once the payment asset is known
+fn execute_mint_nft(
+    deps: DepsMut,
+    env: Env,
+    minter: Addr,
+    payment: AssetInfo,
+    paid: Uint128,
+    mint_price: Uint128,
+    msg: HopeMintMsg,
+) -> Result<Response, ContractError> {
+    let MintPlan {
+        state,
+        mut target,
+        quantity,
+        mint_price,
+        total_price,
+        refund,
+        phase_mints,
+        ..
+    } = plan_mint(
+        deps.as_ref(),
+        &env,
+        &minter,
+        &payment,
+        paid,
+        mint_price,
+        &msg,
+    )?;
+
+    let mut phase = None;
+    if let Some((phase_name, phase_count)) = phase_mints {
+        store_phase_mints(deps.storage, &phase_name, &minter, &phase_count)?;
+        phase = Some(phase_name);
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !total_price.is_zero() {
+        let payout = payout_address(deps.storage, &state)?;
+        messages.push(transfer_msg(&payment, &payout, total_price)?);
+    }
+    if !refund.is_zero() {
+        messages.push(transfer_msg(&payment, minter.as_str(), refund)?);
+    }
+    let price = Asset {
+        info: payment,
+        amount: mint_price,
+    };
+    let mints = mint_tokens(
+        deps.storage,
+        &mut target,
+        &minter,
+        quantity,
+        msg,
+        Some(price),
+        phase,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(mints))
+}
+
+// outcome of the mint checks, applied by execute_mint_nft and reported by SimulateMint
+struct MintPlan {
+    state: State,
+    target: MintTarget,
+    quantity: Uint128,
+    mint_price: Uint128,
+    total_price: Uint128,
+    refund: Uint128,
+    phase_mints: Option<(String, Uint128)>,
+    // wallet limit of the active phase, a merkle allocation included
+    phase_limit: Option<Uint128>,
+}
+
+// runs every check of a mint without writing state
+fn plan_mint(
+    deps: Deps,
+    env: &Env,
+    minter: &Addr,
+    payment: &AssetInfo,
+    paid: Uint128,
+    mint_price: Uint128,
+    msg: &HopeMintMsg,
+) -> Result<MintPlan, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    let quantity = Uint128::from(msg.quantity.unwrap_or(1));
+    if quantity.is_zero() {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if let Some(max_per_tx) = read_max_per_tx(deps.storage) {
+        if quantity > max_per_tx {
+            return Err(ContractError::MaxPerTxExceeded {});
+        }
+    }
 
-    if state.total_nft >= Uint128::new(2000) {
+    // the whole batch has to fit in the collection
+    let target = load_mint_target(deps, &state, msg.nft_addr.as_deref())?;
+    if target.collection.total_nft + quantity > target.collection.max_supply {
         return Err(ContractError::MintEnded {});
     }
 
+    // a sale phase price wins over the collection price, which wins over the registry
+    let mut mint_price = match target
+        .collection
+        .prices
+        .iter()
+        .find(|price| &price.info == payment)
+    {
+        Some(price) => price.amount,
+        None => mint_price,
+    };
+
+    // the current sale phase may restrict who mints, how many and at what price
+    let mut phase_mints = None;
+    let mut phase_limit = None;
+    if let Some(phase) = load_active_phase(deps.storage, env.block.time)? {
+        let mut per_wallet_limit = phase.per_wallet_limit;
+        match &phase.eligibility {
+            Eligibility::Everyone {} => {}
+            Eligibility::Allowlist { addresses } => {
+                if !addresses.iter().any(|address| address == minter.as_str()) {
+                    return Err(ContractError::NotEligible {});
+                }
+            }
+            Eligibility::Merkle { root } => {
+                verify_allowlist_proof(root, minter, &msg.proof, msg.allocation)?;
+                // an allocation in the leaf replaces the phase wide limit
+                if msg.allocation.is_some() {
+                    per_wallet_limit = msg.allocation;
+                }
+            }
+        }
+        if let Some(price) = phase.prices.iter().find(|price| &price.info == payment) {
+            mint_price = price.amount;
+        }
+        let phase_count =
+            read_phase_mints(deps.storage, &phase.name, minter).unwrap_or_default() + quantity;
+        if let Some(per_wallet_limit) = per_wallet_limit {
+            if phase_count > per_wallet_limit {
+                return Err(ContractError::MintExceeded {});
+            }
+        }
+        phase_mints = Some((phase.name, phase_count));
+        phase_limit = per_wallet_limit;
+    }
+
     //nft number of user(for the user who first mint)
-    let mut user_token_count = Uint128::new(0);
-    //address of sender who will possess nft
-    let sender_address = deps.api.addr_validate(cw20_receive_msg.sender.as_str())?;
-    let token_count = read_token_count(deps.storage, &sender_address);
+    let user_token_count = target.wallet_mints(deps.storage, minter) + quantity;
+    if user_token_count > target.collection.per_wallet_limit {
+        return Err(ContractError::MintExceeded {});
+    }
 
-    let maximum_nft = read_maximum_nft(deps.storage)?;
+    // the payment must cover the price of the batch, anything above is refunded
+    let total_price = mint_price.checked_mul(quantity).map_err(StdError::from)?;
+    if paid < total_price {
+        return Err(ContractError::Notenough {});
+    }
+    let refund = paid - total_price;
+
+    Ok(MintPlan {
+        state,
+        target,
+        quantity,
+        mint_price,
+        total_price,
+        refund,
+        phase_mints,
+        phase_limit,
+    })
+}
+
+// collection a mint goes into, the default collection is backed by the state
+struct MintTarget {
+    collection: Collection,
+    default: bool,
+}
 
-    if token_count != None {
-        let user_token_number = token_count.unwrap();
-        if user_token_number >= maximum_nft {
-            return Err(ContractError::MintExceeded {});
+impl MintTarget {
+    fn wallet_mints(&self, storage: &dyn Storage, user: &Addr) -> Uint128 {
+        if self.default {
+            read_token_count(storage, user).unwrap_or_default()
         } else {
-            user_token_count = user_token_number;
+            read_collection_mints(storage, &self.collection.nft_address, user)
         }
     }
 
-    user_token_count += Uint128::new(1);
-
-    let token_address = read_token_address(deps.storage)?;
-    let nft_address = read_nft_address(deps.storage)?;
-
-    let msg: HopeMintMsg = from_binary(&cw20_receive_msg.msg)?;
+    fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        if self.default {
+            let mut state = CONFIG.load(storage)?;
+            state.total_nft = self.collection.total_nft;
+            CONFIG.save(storage, &state)
+        } else {
+            store_collection(storage, &self.collection)
+        }
+    }
 
-    let token_id: String = ["Hope".to_string(), state.total_nft.to_string()].join(".");
-    state.total_nft += Uint128::new(1);
-    config(deps.storage).save(&state)?;
+    fn store_wallet_mints(
+        &self,
+        storage: &mut dyn Storage,
+        user: &Addr,
+        count: Uint128,
+    ) -> StdResult<()> {
+        if self.default {
+            store_token_count(storage, user, count)
+        } else {
+            store_collection_mints(storage, &self.collection.nft_address, user, &count)
+        }
+    }
+}
 
-    let user_nft = read_user_info(deps.storage, &sender_address);
+// resolves HopeMintMsg.nft_addr, no address or the default nft address picks the
+// default collection
+fn load_mint_target(
+    deps: Deps,
+    state: &State,
+    nft_addr: Option<&str>,
+) -> Result<MintTarget, ContractError> {
+    let nft_address = read_nft_address(deps.storage)?;
+    if let Some(nft_addr) = nft_addr {
+        let nft_addr = deps.api.addr_validate(nft_addr)?;
+        if nft_addr != nft_address {
+            return match read_collection(deps.storage, &nft_addr) {
+                Some(collection) => Ok(MintTarget {
+                    collection,
+                    default: false,
+                }),
+                None => Err(ContractError::UnknownCollection {}),
+            };
+        }
+    }
+    Ok(MintTarget {
+        collection: Collection {
+            nft_address,
+            token_prefix: "Hope".to_string(),
+            max_supply: state.max_supply,
+            total_nft: state.total_nft,
+            prices: vec![],
+            per_wallet_limit: read_maximum_nft(deps.storage)?,
+        },
+        default: true,
+    })
+}
 
-    if user_nft == None {
-        let mut owned_nft = vec![];
-        owned_nft.push(token_id.clone());
-        store_users(
-            deps.storage,
-            &sender_address,
-            UserInfo {
-                address: sender_address.to_string(),
-                nft: owned_nft,
-            },
-        )?;
-    } else {
-        let mut owned_nft = read_user_info(deps.storage, &sender_address).unwrap();
-        owned_nft.nft.push(token_id.clone());
-        store_users(
-            deps.storage,
-            &sender_address,
-            UserInfo {
-                address: sender_address.to_string(),
-                nft: owned_nft.nft,
+// assigns the next token ids to the owner and builds the cw721 mint messages
+fn mint_tokens(
+    storage: &mut dyn Storage,
+    target: &mut MintTarget,
+    owner: &Addr,
+    quantity: Uint128,
+    msg: HopeMintMsg,
+    payment: Option<Asset>,
+    phase: Option<String>,
+) -> StdResult<Vec<SubMsg>> {
+    // the counters are bumped up front, a failing cw721 mint reverts them with the
+    // whole transaction since its submessage only replies on success
+    let collection = &mut target.collection;
+    let mut token_ids: Vec<(u64, String)> = vec![];
+    for _ in 0..quantity.u128() {
+        let token_id = collection.token_id(collection.total_nft);
+        let reply_id = MINT_REPLY_ID_OFFSET + next_mint_sequence(storage)?;
+        store_pending_mint(
+            storage,
+            reply_id,
+            &PendingMint {
+                token_id: token_id.clone(),
+                minter: owner.clone(),
+                payment: payment.clone(),
+                phase: phase.clone(),
             },
         )?;
+        token_ids.push((reply_id, token_id));
+        collection.total_nft += Uint128::new(1);
     }
+    let nft_address = collection.nft_address.clone();
+    target.save(storage)?;
+
+    let mut owned_nft = match read_user_info(storage, owner) {
+        Some(user_info) => user_info.nft,
+        None => vec![],
+    };
+    owned_nft.extend(token_ids.iter().map(|(_, token_id)| token_id.clone()));
+    store_users(
+        storage,
+        owner,
+        UserInfo {
+            address: owner.to_string(),
+            nft: owned_nft,
+        },
+    )?;
 
-    store_token_count(deps.storage, &sender_address, user_token_count)?;
+    let user_token_count = target.wallet_mints(storage, owner) + quantity;
+    target.store_wallet_mints(storage, owner, user_token_count)?;
 
     let meta_data = Metadata {
         name: msg.name,
@@ -121,26 +831,111 @@ fn execute_receive(
         init_price: msg.init_price,
     };
 
-    Ok(Response::new()
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: token_address.to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: state.owner,
-                amount: cw20_receive_msg.amount,
-            })?,
-        }))
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+    let mut messages: Vec<SubMsg> = vec![];
+    for (reply_id, token_id) in token_ids {
+        let mint = WasmMsg::Execute {
             contract_addr: nft_address.to_string(),
             msg: to_binary(&Cw721BaseExecuteMsg::Mint(MintMsg {
                 //::<Metadata>
-                token_id: token_id.clone(),
-                owner: cw20_receive_msg.sender,
-                token_uri: msg.image_uri,
+                token_id,
+                owner: owner.to_string(),
+                token_uri: msg.image_uri.clone(),
                 extension: meta_data.clone(),
             }))?,
             funds: vec![],
-        })))
+        };
+        messages.push(SubMsg::reply_on_success(mint, reply_id));
+    }
+    Ok(messages)
+}
+
+fn verify_allowlist_proof(
+    root: &str,
+    minter: &Addr,
+    proof: &Option<Vec<String>>,
+    allocation: Option<Uint128>,
+) -> Result<(), ContractError> {
+    let root = decode_hash(root).ok_or(ContractError::InvalidMerkleRoot {})?;
+    let proof = proof
+        .iter()
+        .flatten()
+        .map(|hash| decode_hash(hash).ok_or(ContractError::InvalidProof {}))
+        .collect::<Result<Vec<Hash>, ContractError>>()?;
+    let allocation = allocation.map(|allocation| allocation.to_string());
+    let leaf = leaf_hash(minter.as_str(), allocation.as_deref());
+    if !verify_proof(leaf, &proof, &root) {
+        return Err(ContractError::NotEligible {});
+    }
+    Ok(())
+}
+
+// no configured phases keeps minting open, otherwise one has to be running
+fn load_active_phase(
+    storage: &dyn Storage,
+    time: Timestamp,
+) -> Result<Option<Phase>, ContractError> {
+    let phases = read_phases(storage)?;
+    if phases.is_empty() {
+        return Ok(None);
+    }
+    for phase in phases.iter() {
+        if time < phase.start {
+            return Err(ContractError::PhaseNotStarted { start: phase.start });
+        }
+        match phase.end {
+            Some(end) if time >= end => continue,
+            _ => return Ok(Some(phase.clone())),
+        }
+    }
+    let end = phases.last().and_then(|phase| phase.end).unwrap_or(time);
+    Err(ContractError::PhaseEnded { end })
+}
+
+fn ensure_owner(state: &State, sender: &Addr) -> Result<(), ContractError> {
+    match &state.owner {
+        Some(owner) if owner == sender => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+// the owner holds every role, everyone else needs an explicit grant
+fn ensure_role(storage: &dyn Storage, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    let state = CONFIG.load(storage)?;
+    if ensure_owner(&state, sender).is_ok() || read_roles(storage, sender).contains(&role) {
+        return Ok(());
+    }
+    Err(ContractError::Unauthorized {})
+}
+
+// proceeds go to the owner unless a payout address has been recorded
+fn payout_address(storage: &dyn Storage, state: &State) -> StdResult<String> {
+    match read_payout_address(storage)? {
+        Some(payout) => Ok(payout.to_string()),
+        None => state
+            .owner
+            .clone()
+            .ok_or_else(|| StdError::not_found("payout address")),
+    }
+}
+
+fn transfer_msg(asset: &AssetInfo, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    match asset {
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+        })),
+        AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        })),
+    }
 }
 
 fn execute_maxium_nft(
@@ -148,76 +943,638 @@ fn execute_maxium_nft(
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let state = config_read(deps.storage).load()?;
-    if state.owner != info.sender.to_string() {
-        return Err(ContractError::Unauthorized {});
-    }
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
     store_maximum_nft(deps.storage, &amount)?;
     Ok(Response::default())
 }
 
-fn execute_set_address(
+fn execute_add_payment_token(
     deps: DepsMut,
     info: MessageInfo,
     address: String,
+    price: Uint128,
 ) -> Result<Response, ContractError> {
-    let state = config_read(deps.storage).load()?;
-    if state.owner != info.sender.to_string() {
-        return Err(ContractError::Unauthorized {});
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if price.is_zero() {
+        return Err(invalid_config("mint price must be greater than zero"));
     }
     let token_address = deps.api.addr_validate(&address)?;
-    store_token_address(deps.storage, &token_address)?;
+    store_payment_token(deps.storage, &token_address, &price)?;
     Ok(Response::default())
 }
 
-fn execute_set_nft_address(
+fn execute_remove_payment_token(
     deps: DepsMut,
     info: MessageInfo,
     address: String,
 ) -> Result<Response, ContractError> {
-    let state = config_read(deps.storage).load()?;
-    if state.owner != info.sender.to_string() {
-        return Err(ContractError::Unauthorized {});
-    }
-    let nft_address = deps.api.addr_validate(&address)?;
-    store_nft_address(deps.storage, &nft_address)?;
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let token_address = deps.api.addr_validate(&address)?;
+    remove_payment_token(deps.storage, &token_address);
     Ok(Response::default())
 }
 
-pub fn execute_buy_token(
+fn execute_set_native_price(
     deps: DepsMut,
-    // env:Env,
     info: MessageInfo,
-    amount: i32,
+    denom: String,
+    price: Uint128,
 ) -> Result<Response, ContractError> {
-    let token_address = read_token_address(deps.storage)?;
-    let res = Response::new()
-        .add_attribute("action", "buy")
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: token_address.to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: String::from(info.sender.as_str()),
-                amount: Uint128::new(amount as u128),
-            })?,
-        }));
-    Ok(res)
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if price.is_zero() {
+        return Err(invalid_config("mint price must be greater than zero"));
+    }
+    store_native_price(deps.storage, &denom, &price)?;
+    Ok(Response::default())
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetBalance { address } => to_binary(&query_get_balance(deps, address)?),
-        QueryMsg::GetTokenAddress {} => to_binary(&query_get_address(deps)?),
-        QueryMsg::GetAllUsers {} => to_binary(&query_get_users(deps)?),
-        QueryMsg::GetUserInfo { address } => to_binary(&query_user_info(deps, address)?),
-        QueryMsg::GetContractInfo {} => to_binary(&query_contract_info(deps)?),
-        QueryMsg::GetTokenInfo { address } => to_binary(&query_token_info(deps, address)?),
-        QueryMsg::GetTokenCount { address } => to_binary(&query_token_count(deps, address)?),
-        QueryMsg::GetNftAddress {} => to_binary(&query_nft_address(deps)?),
-        QueryMsg::GetMaximumNft {} => to_binary(&query_maximum_nft(deps)?),
-        QueryMsg::GetStateInfo {} => to_binary(& query_get_info(deps)?)
-    }
+fn execute_remove_native_price(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    remove_native_price(deps.storage, &denom);
+    Ok(Response::default())
+}
+
+fn execute_set_max_per_tx(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    // a zero limit would silently stop every mint, pausing is explicit
+    if amount.is_zero() {
+        return Err(invalid_config("max per tx must be greater than zero"));
+    }
+    store_max_per_tx(deps.storage, &amount)?;
+    Ok(Response::default())
+}
+
+fn execute_set_max_supply(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let mut state = CONFIG.load(deps.storage)?;
+    if amount > state.max_supply || amount < state.total_nft {
+        return Err(ContractError::InvalidSupply {});
+    }
+    state.max_supply = amount;
+    CONFIG.save(deps.storage, &state)?;
+    Ok(Response::default())
+}
+
+fn execute_set_phases(
+    deps: DepsMut,
+    info: MessageInfo,
+    phases: Vec<Phase>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    for (i, phase) in phases.iter().enumerate() {
+        if let Some(end) = phase.end {
+            if end <= phase.start {
+                return Err(ContractError::InvalidPhases {});
+            }
+        }
+        if let Some(next) = phases.get(i + 1) {
+            match phase.end {
+                Some(end) if end <= next.start => {}
+                _ => return Err(ContractError::InvalidPhases {}),
+            }
+        }
+        if phases[..i].iter().any(|other| other.name == phase.name) {
+            return Err(ContractError::InvalidPhases {});
+        }
+        match &phase.eligibility {
+            Eligibility::Everyone {} => {}
+            Eligibility::Allowlist { addresses } => {
+                for address in addresses {
+                    deps.api.addr_validate(address)?;
+                }
+            }
+            Eligibility::Merkle { root } => {
+                decode_hash(root).ok_or(ContractError::InvalidMerkleRoot {})?;
+            }
+        }
+    }
+    store_phases(deps.storage, &phases)?;
+    Ok(Response::default())
+}
+
+fn execute_set_merkle_root(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase: String,
+    root: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    decode_hash(&root).ok_or(ContractError::InvalidMerkleRoot {})?;
+    let mut phases = read_phases(deps.storage)?;
+    let phase = phases
+        .iter_mut()
+        .find(|p| p.name == phase)
+        .ok_or_else(|| StdError::not_found("phase"))?;
+    phase.eligibility = Eligibility::Merkle { root };
+    store_phases(deps.storage, &phases)?;
+    Ok(Response::default())
+}
+
+fn execute_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    reason: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Operator)?;
+    let mut state = CONFIG.load(deps.storage)?;
+    state.paused = true;
+    state.pause_reason = reason;
+    CONFIG.save(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("action", "pause"))
+}
+
+fn execute_unpause(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Operator)?;
+    let mut state = CONFIG.load(deps.storage)?;
+    state.paused = false;
+    state.pause_reason = None;
+    CONFIG.save(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("action", "unpause"))
+}
+
+fn execute_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    let new_owner = deps.api.addr_validate(&owner)?;
+    if let Some(expiry) = expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::OwnershipProposalExpired {});
+        }
+    }
+    store_pending_owner(
+        deps.storage,
+        &PendingOwner {
+            address: new_owner.to_string(),
+            expiry,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("pending_owner", new_owner))
+}
+
+fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = read_pending_owner(deps.storage)?.ok_or(ContractError::NoPendingOwner {})?;
+    if pending.address != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(expiry) = pending.expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::OwnershipProposalExpired {});
+        }
+    }
+    let mut state = CONFIG.load(deps.storage)?;
+    state.owner = Some(pending.address);
+    CONFIG.save(deps.storage, &state)?;
+    remove_pending_owner(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    if read_pending_owner(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    remove_pending_owner(deps.storage);
+    Ok(Response::new().add_attribute("action", "cancel_ownership_proposal"))
+}
+
+fn execute_renounce_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = CONFIG.load(deps.storage)?;
+    ensure_owner(&state, &info.sender)?;
+    // keep paying the last owner once nobody owns the contract
+    if read_payout_address(deps.storage)?.is_none() {
+        store_payout_address(deps.storage, &info.sender)?;
+    }
+    state.owner = None;
+    CONFIG.save(deps.storage, &state)?;
+    remove_pending_owner(deps.storage);
+    // roles are granted by the owner's authority and go with it
+    clear_roles(deps.storage)?;
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
+}
+
+fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let grantee = deps.api.addr_validate(&address)?;
+    let mut roles = read_roles(deps.storage, &grantee);
+    if !roles.contains(&role) {
+        roles.push(role.clone());
+        store_roles(deps.storage, &grantee, &roles)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("address", grantee)
+        .add_attribute("role", role.to_string()))
+}
+
+fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let grantee = deps.api.addr_validate(&address)?;
+    let mut roles = read_roles(deps.storage, &grantee);
+    roles.retain(|r| r != &role);
+    store_roles(deps.storage, &grantee, &roles)?;
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("address", grantee)
+        .add_attribute("role", role.to_string()))
+}
+
+fn execute_airdrop(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<String>,
+    msg: HopeMintMsg,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Operator)?;
+    let state = CONFIG.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    let quantity = Uint128::from(msg.quantity.unwrap_or(1));
+    if quantity.is_zero() {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    let total = quantity
+        .checked_mul(Uint128::from(recipients.len() as u128))
+        .map_err(StdError::from)?;
+    let mut target = load_mint_target(deps.as_ref(), &state, msg.nft_addr.as_deref())?;
+    if target.collection.total_nft + total > target.collection.max_supply {
+        return Err(ContractError::MintEnded {});
+    }
+
+    let mut mints: Vec<SubMsg> = vec![];
+    for recipient in recipients {
+        let recipient = deps.api.addr_validate(&recipient)?;
+        mints.extend(mint_tokens(
+            deps.storage,
+            &mut target,
+            &recipient,
+            quantity,
+            msg.clone(),
+            None,
+            None,
+        )?);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "airdrop")
+        .add_submessages(mints))
+}
+
+fn execute_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: Asset,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Treasurer)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+    Ok(Response::new()
+        .add_attribute("action", "withdraw")
+        .add_attribute("recipient", recipient.as_str())
+        .add_message(transfer_msg(&asset.info, recipient.as_str(), asset.amount)?))
+}
+
+fn execute_set_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let token_address = deps.api.addr_validate(&address)?;
+    store_token_address(deps.storage, &token_address)?;
+    Ok(Response::default())
+}
+
+fn execute_set_nft_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let nft_address = deps.api.addr_validate(&address)?;
+    // a registered collection would be shadowed by the default one
+    if read_collection(deps.storage, &nft_address).is_some() {
+        return Err(invalid_config("the nft address is a registered collection"));
+    }
+    store_nft_address(deps.storage, &nft_address)?;
+    Ok(Response::default())
+}
+
+pub fn execute_buy_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    let (exchange, cost) = compute_purchase(deps.storage, Some(&info.sender), amount)?;
+
+    // exactly one coin of the exchange denom pays for the tokens
+    if info.funds.len() != 1 || info.funds[0].denom != exchange.denom {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let paid = info.funds[0].amount;
+    if paid < cost {
+        return Err(ContractError::Notenough {});
+    }
+    let refund = paid - cost;
+
+    let total_issued = read_total_issued(deps.storage) + amount;
+    store_total_issued(deps.storage, &total_issued)?;
+    let issued = read_issued(deps.storage, &info.sender) + amount;
+    store_issued(deps.storage, &info.sender, &issued)?;
+
+    let token_address = read_token_address(deps.storage)?;
+    let mut res = Response::new()
+        .add_attribute("action", "buy")
+        .add_attribute("amount", amount)
+        .add_attribute("cost", cost)
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: String::from(info.sender.as_str()),
+                amount,
+            })?,
+        }));
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: exchange.denom,
+                amount: refund,
+            }],
+        });
+    }
+    Ok(res)
+}
+
+// checks the issuance caps and prices a purchase, rounding the cost up
+fn compute_purchase(
+    storage: &dyn Storage,
+    buyer: Option<&Addr>,
+    amount: Uint128,
+) -> Result<(ExchangeConfig, Uint128), ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    let exchange = read_exchange(storage)?;
+    match read_total_issued(storage).checked_add(amount) {
+        Ok(total_issued) if total_issued <= exchange.issuance_cap => {}
+        _ => return Err(ContractError::IssuanceCapExceeded {}),
+    }
+    if let Some(buyer) = buyer {
+        match read_issued(storage, buyer).checked_add(amount) {
+            Ok(issued) if issued <= exchange.per_address_cap => {}
+            _ => return Err(ContractError::PurchaseCapExceeded {}),
+        }
+    }
+
+    let numerator = amount.full_mul(exchange.price.numerator());
+    let denominator = Uint256::from(exchange.price.denominator());
+    let mut cost = numerator / denominator;
+    if !(numerator % denominator).is_zero() {
+        cost += Uint256::from(1u8);
+    }
+    let cost = Uint128::try_from(cost).map_err(StdError::from)?;
+    Ok((exchange, cost))
+}
+
+fn execute_set_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    nft_address: String,
+    token_prefix: String,
+    max_supply: Uint128,
+    prices: Vec<Asset>,
+    per_wallet_limit: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let nft_address = deps.api.addr_validate(&nft_address)?;
+    if read_nft_address(deps.storage).ok().as_ref() == Some(&nft_address) {
+        return Err(invalid_config("the default collection can't be registered"));
+    }
+    if per_wallet_limit.is_zero() || per_wallet_limit > max_supply {
+        return Err(invalid_config(
+            "per wallet limit must be between one and the max supply",
+        ));
+    }
+    if prices.iter().any(|price| price.amount.is_zero()) {
+        return Err(invalid_config("mint price must be greater than zero"));
+    }
+    // token ids of all collections end up in the same user lists and mint ledger
+    if token_prefix.is_empty() || token_prefix == "Hope" {
+        return Err(invalid_config("token prefix is empty or reserved"));
+    }
+    if let Some(owner) = read_token_prefix(deps.storage, &token_prefix)? {
+        if owner != nft_address {
+            return Err(invalid_config("token prefix is already used"));
+        }
+    }
+
+    // an existing or previously removed collection keeps its counter
+    let total_nft = read_collection_counter(deps.storage, &nft_address);
+    if max_supply < total_nft {
+        return Err(ContractError::InvalidSupply {});
+    }
+    store_token_prefix(deps.storage, &token_prefix, &nft_address)?;
+    store_collection(
+        deps.storage,
+        &Collection {
+            nft_address: nft_address.clone(),
+            token_prefix,
+            max_supply,
+            total_nft,
+            prices,
+            per_wallet_limit,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set_collection")
+        .add_attribute("nft_address", nft_address))
+}
+
+fn execute_remove_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    nft_address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    let nft_address = deps.api.addr_validate(&nft_address)?;
+    remove_collection(deps.storage, &nft_address)?;
+    Ok(Response::new()
+        .add_attribute("action", "remove_collection")
+        .add_attribute("nft_address", nft_address))
+}
+
+fn execute_set_redemption(
+    deps: DepsMut,
+    info: MessageInfo,
+    redemption: RedemptionConfig,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if redemption.spread > Decimal::one() {
+        return Err(ContractError::InvalidSpread {});
+    }
+    if redemption.period == 0 {
+        return Err(invalid_config(
+            "redemption period must be greater than zero",
+        ));
+    }
+    store_redemption(deps.storage, &redemption)?;
+    Ok(Response::new().add_attribute("action", "set_redemption"))
+}
+
+fn execute_set_exchange(
+    deps: DepsMut,
+    info: MessageInfo,
+    exchange: ExchangeConfig,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, Role::Admin)?;
+    if exchange.price.is_zero() {
+        return Err(invalid_config("exchange price must be greater than zero"));
+    }
+    store_exchange(deps.storage, &exchange)?;
+    Ok(Response::new().add_attribute("action", "set_exchange"))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetBalance { address } => to_binary(&query_get_balance(deps, address)?),
+        QueryMsg::GetTokenAddress {} => to_binary(&query_get_address(deps)?),
+        QueryMsg::GetAllUsers { start_after, limit } => {
+            to_binary(&query_get_users(deps, start_after, limit)?)
+        }
+        QueryMsg::GetUsersInfo { start_after, limit } => {
+            to_binary(&query_users_info(deps, start_after, limit)?)
+        }
+        QueryMsg::GetUserCount {} => to_binary(&query_user_count(deps)?),
+        QueryMsg::GetUserInfo { address } => to_binary(&query_user_info(deps, address)?),
+        QueryMsg::GetTopMinters { limit } => to_binary(&query_top_minters(deps, limit)?),
+        QueryMsg::GetContractInfo {} => to_binary(&query_contract_info(deps)?),
+        QueryMsg::GetTokenInfo {
+            address,
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_token_info(
+            deps,
+            address,
+            collection,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetTokensDetailed {
+            address,
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_tokens_detailed(
+            deps,
+            address,
+            collection,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetTokenCount {
+            address,
+            collection,
+        } => to_binary(&query_token_count(deps, address, collection)?),
+        QueryMsg::GetNftAddress {} => to_binary(&query_nft_address(deps)?),
+        QueryMsg::GetMaximumNft {} => to_binary(&query_maximum_nft(deps)?),
+        QueryMsg::GetMaxPerTx {} => to_binary(&query_max_per_tx(deps)?),
+        QueryMsg::GetMintPrice { asset } => to_binary(&query_mint_price(deps, asset)?),
+        QueryMsg::GetPaymentTokens { start_after, limit } => {
+            to_binary(&query_payment_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::GetNativePrices {} => to_binary(&query_native_prices(deps)?),
+        QueryMsg::GetStateInfo { collection } => to_binary(&query_get_info(deps, collection)?),
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetMintStatus {
+            collection,
+            address,
+            asset,
+        } => to_binary(&query_mint_status(deps, env, collection, address, asset)?),
+        QueryMsg::GetCollections { start_after, limit } => {
+            to_binary(&query_collections(deps, start_after, limit)?)
+        }
+        QueryMsg::GetMintRecord { token_id } => to_binary(&query_mint_record(deps, token_id)?),
+        QueryMsg::GetMintHistory {
+            minter,
+            start_time,
+            end_time,
+            start_after,
+            limit,
+        } => to_binary(&query_mint_history(
+            deps,
+            minter,
+            start_time,
+            end_time,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetPhases {} => to_binary(&query_phases(deps)?),
+        QueryMsg::GetActivePhase {} => to_binary(&query_active_phase(deps, env)?),
+        QueryMsg::GetOwnership {} => to_binary(&query_ownership(deps)?),
+        QueryMsg::GetRoles {} => to_binary(&query_roles(deps)?),
+        QueryMsg::GetExchange {} => to_binary(&query_exchange(deps)?),
+        QueryMsg::GetTreasury {} => to_binary(&query_treasury(deps, env)?),
+        QueryMsg::SimulateBuyToken { amount, address } => {
+            to_binary(&query_simulate_buy_token(deps, amount, address)?)
+        }
+        QueryMsg::SimulateMint {
+            sender,
+            amount,
+            msg,
+            token,
+        } => to_binary(&query_simulate_mint(deps, env, sender, amount, msg, token)?),
+    }
 }
 
 pub fn query_contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
@@ -229,37 +1586,90 @@ pub fn query_contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
     Ok(contract_info)
 }
 
-pub fn query_token_count(deps: Deps, address: String) -> StdResult<TokenCount> {
+pub fn query_token_count(
+    deps: Deps,
+    address: String,
+    collection: Option<String>,
+) -> StdResult<TokenCount> {
     let user_address = deps.api.addr_validate(address.as_str())?;
-    let state = config_read(deps.storage).load()?;
-    let result = read_token_count(deps.storage, &user_address);
-    if result == None {
-        Ok(TokenCount {
-            total_nft: state.total_nft,
-            owned_nft_number: Uint128::new(0),
-        })
-    } else {
-        Ok(TokenCount {
-            total_nft: state.total_nft,
-            owned_nft_number: result.unwrap(),
-        })
-    }
+    let state = CONFIG.load(deps.storage)?;
+    let target = load_mint_target(deps, &state, collection.as_deref())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(TokenCount {
+        total_nft: target.collection.total_nft,
+        owned_nft_number: target.wallet_mints(deps.storage, &user_address),
+    })
+}
+
+pub fn query_collections(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Collection>> {
+    let start_after = match start_after {
+        Some(address) => Some(deps.api.addr_validate(&address)?),
+        None => None,
+    };
+    read_collections(deps.storage, start_after, limit)
+}
+
+// nft contract of a collection, none picks the default collection
+fn collection_nft_address(deps: Deps, collection: Option<&str>) -> StdResult<Addr> {
+    let state = CONFIG.load(deps.storage)?;
+    let target = load_mint_target(deps, &state, collection)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(target.collection.nft_address)
 }
 
-pub fn query_token_info(deps: Deps, address: String) -> StdResult<TokensResponse> {
+pub fn query_token_info(
+    deps: Deps,
+    address: String,
+    collection: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
     let owner = deps.api.addr_validate(&address)?;
-    let nft_address = read_nft_address(deps.storage)?;
+    let nft_address = collection_nft_address(deps, collection.as_deref())?;
     let balance = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: nft_address.to_string(),
         msg: to_binary(&Cw721BaseQueryMsg::Tokens {
             owner: owner.to_string(),
-            start_after: None,
-            limit: None,
+            start_after,
+            limit,
         })?,
     }))?;
     Ok(balance)
 }
 
+// a page of the owner's tokens together with their uri and metadata
+pub fn query_tokens_detailed(
+    deps: Deps,
+    address: String,
+    collection: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensDetailedResponse> {
+    let nft_address = collection_nft_address(deps, collection.as_deref())?;
+    let page = query_token_info(deps, address, collection, start_after, limit)?;
+    let mut tokens = vec![];
+    for token_id in page.tokens {
+        let info: AllNftInfoResponse<Metadata> =
+            deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: nft_address.to_string(),
+                msg: to_binary(&Cw721BaseQueryMsg::AllNftInfo {
+                    token_id: token_id.clone(),
+                    include_expired: None,
+                })?,
+            }))?;
+        tokens.push(TokenDetail {
+            token_id,
+            token_uri: info.info.token_uri,
+            extension: info.info.extension,
+        });
+    }
+    Ok(TokensDetailedResponse { tokens })
+}
+
 pub fn query_get_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
     deps.api.addr_validate(&address)?;
     let token_address = read_token_address(deps.storage)?;
@@ -282,57 +1692,492 @@ pub fn query_nft_address(deps: Deps) -> StdResult<String> {
     Ok(result)
 }
 
-pub fn query_get_users(deps: Deps) -> StdResult<Vec<String>> {
-    let users = read_users(deps.storage)?;
+pub fn query_get_users(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let users = read_users(deps.storage, start_after, limit)?;
     Ok(users)
 }
 
-pub fn query_user_info(deps: Deps, address: String) -> StdResult<UserInfo> {
-    let user = read_user_info(deps.storage, &deps.api.addr_validate(&address)?).unwrap();
+pub fn query_users_info(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<UserInfo>> {
+    read_users_info(deps.storage, start_after, limit)
+}
+
+// number of addresses that minted at least once
+pub fn query_user_count(deps: Deps) -> StdResult<u64> {
+    Ok(read_unique_minters(deps.storage))
+}
+
+// none for addresses that never minted
+pub fn query_user_info(deps: Deps, address: String) -> StdResult<Option<UserInfo>> {
+    let user = read_user_info(deps.storage, &deps.api.addr_validate(&address)?);
     Ok(user)
 }
 
+pub fn query_top_minters(deps: Deps, limit: Option<u32>) -> StdResult<Vec<UserInfo>> {
+    read_top_minters(deps.storage, limit)
+}
+
 pub fn query_maximum_nft(deps: Deps) -> StdResult<Uint128> {
     let maximum_nft = read_maximum_nft(deps.storage)?;
     Ok(maximum_nft)
 }
 
-pub fn query_get_info(deps:Deps) -> StdResult<State>{
-    let state =  config_read(deps.storage).load()?;
-    Ok(state)
+pub fn query_max_per_tx(deps: Deps) -> StdResult<Option<Uint128>> {
+    Ok(read_max_per_tx(deps.storage))
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::state::Royalty;
+pub fn query_mint_price(deps: Deps, asset: AssetInfo) -> StdResult<Uint128> {
+    let mint_price = match asset {
+        AssetInfo::Token { contract_addr } => {
+            read_payment_token(deps.storage, &deps.api.addr_validate(&contract_addr)?)
+        }
+        AssetInfo::NativeToken { denom } => read_native_price(deps.storage, &denom),
+    };
+    mint_price.ok_or_else(|| StdError::not_found("mint price"))
+}
 
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{CosmosMsg};
+pub fn query_payment_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PaymentToken>> {
+    let start_after = match start_after {
+        Some(address) => Some(deps.api.addr_validate(&address)?),
+        None => None,
+    };
+    read_payment_tokens(deps.storage, start_after, limit)
+}
 
-    #[test]
-    fn buy_token() {
-        let mut deps = mock_dependencies();
-        let instantiate_msg = InstantiateMsg {};
-        let info = mock_info("creator", &[]);
-        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
-        assert_eq!(0, res.messages.len());
+pub fn query_native_prices(deps: Deps) -> StdResult<Vec<Coin>> {
+    let prices = read_native_prices(deps.storage)?
+        .into_iter()
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect();
+    Ok(prices)
+}
 
-        let info = mock_info("creator", &[]);
-        let address = String::from("token_address");
-        let message = ExecuteMsg::SetTokenAddress { address: address };
-        execute(deps.as_mut(), mock_env(), info, message).unwrap();
+pub fn query_mint_record(deps: Deps, token_id: String) -> StdResult<Option<MintRecord>> {
+    Ok(read_mint_record(deps.storage, &token_id))
+}
+
+pub fn query_mint_history(
+    deps: Deps,
+    minter: Option<String>,
+    start_time: Option<Timestamp>,
+    end_time: Option<Timestamp>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MintRecord>> {
+    let minter = match minter {
+        Some(minter) => Some(deps.api.addr_validate(&minter)?),
+        None => None,
+    };
+    read_mint_history(
+        deps.storage,
+        minter,
+        start_time,
+        end_time,
+        start_after,
+        limit,
+    )
+}
+
+pub fn query_phases(deps: Deps) -> StdResult<Vec<Phase>> {
+    read_phases(deps.storage)
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = CONFIG.may_load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: state.as_ref().and_then(|state| state.owner.clone()),
+        token_address: read_token_address(deps.storage).ok().map(String::from),
+        nft_address: read_nft_address(deps.storage).ok().map(String::from),
+        payout_address: read_payout_address(deps.storage)?.map(String::from),
+        max_supply: state.map(|state| state.max_supply),
+        maximum_nft: read_maximum_nft(deps.storage).ok(),
+        max_per_tx: read_max_per_tx(deps.storage),
+    })
+}
+
+// everything a mint page needs in one query
+pub fn query_mint_status(
+    deps: Deps,
+    env: Env,
+    collection: Option<String>,
+    address: Option<String>,
+    asset: Option<AssetInfo>,
+) -> StdResult<MintStatusResponse> {
+    let config = query_config(deps)?;
+    let state = CONFIG.may_load(deps.storage)?;
+    let phase = load_active_phase(deps.storage, env.block.time).unwrap_or(None);
+    let target = match &state {
+        Some(state) => Some(
+            load_mint_target(deps, state, collection.as_deref())
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        None => None,
+    };
+
+    // the same precedence as a mint: phase, then collection, then registry prices
+    let mut prices: Vec<Asset> = read_all_payment_tokens(deps.storage)?
+        .into_iter()
+        .map(|(address, amount)| Asset {
+            info: AssetInfo::Token {
+                contract_addr: address.to_string(),
+            },
+            amount,
+        })
+        .chain(
+            read_native_prices(deps.storage)?
+                .into_iter()
+                .map(|(denom, amount)| Asset {
+                    info: AssetInfo::NativeToken { denom },
+                    amount,
+                }),
+        )
+        .collect();
+    let collection_prices = target.iter().flat_map(|target| &target.collection.prices);
+    let phase_prices = phase.iter().flat_map(|phase| &phase.prices);
+    for override_price in collection_prices.chain(phase_prices) {
+        let price = prices
+            .iter_mut()
+            .find(|price| price.info == override_price.info);
+        if let Some(price) = price {
+            price.amount = override_price.amount;
+        }
+    }
+
+    let (minted, remaining) = match &target {
+        Some(target) => (
+            target.collection.total_nft,
+            target
+                .collection
+                .max_supply
+                .saturating_sub(target.collection.total_nft),
+        ),
+        None => (Uint128::zero(), Uint128::zero()),
+    };
+    let wallet = match address {
+        Some(address) => {
+            let address = deps.api.addr_validate(&address)?;
+            let price = match &asset {
+                Some(asset) => prices.iter().find(|price| &price.info == asset),
+                None => prices.first(),
+            };
+            Some(wallet_status(deps, &env, address, collection, price))
+        }
+        None => None,
+    };
+
+    Ok(MintStatusResponse {
+        config,
+        phase,
+        prices,
+        minted,
+        remaining,
+        paused: matches!(&state, Some(state) if state.paused),
+        pause_reason: state.and_then(|state| state.pause_reason),
+        wallet,
+    })
+}
+
+// how many more nfts an address can mint, from the checks of a single nft mint
+fn wallet_status(
+    deps: Deps,
+    env: &Env,
+    address: Addr,
+    collection: Option<String>,
+    price: Option<&Asset>,
+) -> WalletStatus {
+    let target = CONFIG
+        .load(deps.storage)
+        .ok()
+        .and_then(|state| load_mint_target(deps, &state, collection.as_deref()).ok());
+    let minted = match &target {
+        Some(target) => target.wallet_mints(deps.storage, &address),
+        None => Uint128::zero(),
+    };
+    let status = |mintable: Uint128, reason: Option<ContractError>| WalletStatus {
+        address: address.to_string(),
+        asset: price.map(|price| price.info.clone()),
+        minted,
+        mintable,
+        reason: reason.map(|err| err.to_string()),
+    };
+
+    // a payment that isn't accepted never gets past the payment lookup
+    let price = match price {
+        Some(price) => price,
+        None => return status(Uint128::zero(), Some(ContractError::InvalidToken {})),
+    };
+    let msg = HopeMintMsg {
+        name: None,
+        image_uri: None,
+        external_link: None,
+        description: None,
+        royalties: None,
+        init_price: None,
+        nft_addr: collection,
+        quantity: Some(1),
+        proof: None,
+        allocation: None,
+    };
+    // the payment always covers the price, only the mint limits are checked
+    let plan = match plan_mint(
+        deps,
+        env,
+        &address,
+        &price.info,
+        Uint128::MAX,
+        price.amount,
+        &msg,
+    ) {
+        Ok(plan) => plan,
+        Err(err) => return status(Uint128::zero(), Some(err)),
+    };
+
+    let collection = &plan.target.collection;
+    let mut mintable = collection
+        .max_supply
+        .saturating_sub(collection.total_nft)
+        .min(collection.per_wallet_limit.saturating_sub(minted));
+    if let (Some((_, phase_count)), Some(phase_limit)) = (&plan.phase_mints, plan.phase_limit) {
+        // the planned count already includes the simulated nft
+        let phase_minted = *phase_count - plan.quantity;
+        mintable = mintable.min(phase_limit.saturating_sub(phase_minted));
+    }
+    status(mintable, None)
+}
+
+pub fn query_active_phase(deps: Deps, env: Env) -> StdResult<Option<Phase>> {
+    // outside of any phase there is simply no active one
+    Ok(load_active_phase(deps.storage, env.block.time).unwrap_or(None))
+}
+
+pub fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let state = CONFIG.load(deps.storage)?;
+    Ok(OwnershipResponse {
+        owner: state.owner,
+        pending_owner: read_pending_owner(deps.storage)?,
+    })
+}
+
+pub fn query_roles(deps: Deps) -> StdResult<Vec<RoleInfo>> {
+    let roles = read_all_roles(deps.storage)?
+        .into_iter()
+        .filter(|(_, roles)| !roles.is_empty())
+        .map(|(address, roles)| RoleInfo {
+            address: address.to_string(),
+            roles,
+        })
+        .collect();
+    Ok(roles)
+}
+
+pub fn query_exchange(deps: Deps) -> StdResult<ExchangeConfig> {
+    read_exchange(deps.storage)
+}
+
+pub fn query_treasury(deps: Deps, env: Env) -> StdResult<TreasuryResponse> {
+    let reserves = deps.querier.query_all_balances(&env.contract.address)?;
+    let redemption = read_redemption(deps.storage).ok();
+    let (redeemed_in_period, remaining_in_period) = match &redemption {
+        Some(redemption) => {
+            let window = current_redemption_window(deps.storage, redemption, env.block.time);
+            (
+                window.redeemed,
+                redemption.period_limit.saturating_sub(window.redeemed),
+            )
+        }
+        None => (Uint128::zero(), Uint128::zero()),
+    };
+    Ok(TreasuryResponse {
+        reserves,
+        redemption,
+        redeemed_in_period,
+        remaining_in_period,
+    })
+}
+
+pub fn query_simulate_buy_token(
+    deps: Deps,
+    amount: Uint128,
+    address: Option<String>,
+) -> StdResult<SimulateBuyTokenResponse> {
+    let buyer = match address {
+        Some(address) => Some(deps.api.addr_validate(&address)?),
+        None => None,
+    };
+    let (exchange, cost) = compute_purchase(deps.storage, buyer.as_ref(), amount)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(SimulateBuyTokenResponse {
+        amount,
+        cost: Coin {
+            denom: exchange.denom,
+            amount: cost,
+        },
+    })
+}
+
+pub fn query_simulate_mint(
+    deps: Deps,
+    env: Env,
+    sender: String,
+    amount: Uint128,
+    msg: HopeMintMsg,
+    token: Option<String>,
+) -> StdResult<SimulateMintResponse> {
+    simulate_mint(deps, &env, &sender, amount, &msg, token)
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+// mirrors execute_receive for a mint hook
+fn simulate_mint(
+    deps: Deps,
+    env: &Env,
+    sender: &str,
+    amount: Uint128,
+    msg: &HopeMintMsg,
+    token: Option<String>,
+) -> Result<SimulateMintResponse, ContractError> {
+    let token = match token {
+        Some(token) => deps.api.addr_validate(&token)?,
+        None => read_token_address(deps.storage)?,
+    };
+    let mint_price = match read_payment_token(deps.storage, &token) {
+        Some(price) => price,
+        None => return Err(ContractError::InvalidToken {}),
+    };
+    let minter = deps.api.addr_validate(sender)?;
+    let payment = AssetInfo::Token {
+        contract_addr: token.to_string(),
+    };
+
+    let plan = plan_mint(deps, env, &minter, &payment, amount, mint_price, msg)?;
+    let collection = &plan.target.collection;
+    let token_ids = (0..plan.quantity.u128())
+        .map(|i| collection.token_id(collection.total_nft + Uint128::new(i)))
+        .collect();
+    Ok(SimulateMintResponse {
+        token_ids,
+        mint_price: plan.mint_price,
+        payout: plan.total_price,
+        payout_address: payout_address(deps.storage, &plan.state)?,
+        refund: plan.refund,
+    })
+}
+
+pub fn query_get_info(deps: Deps, collection: Option<String>) -> StdResult<StateResponse> {
+    let state = CONFIG.load(deps.storage)?;
+    let (total_nft, max_supply) = match collection {
+        Some(collection) => {
+            let target = load_mint_target(deps, &state, Some(&collection))
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            (target.collection.total_nft, target.collection.max_supply)
+        }
+        None => (state.total_nft, state.max_supply),
+    };
+    Ok(StateResponse {
+        remaining_supply: max_supply.saturating_sub(total_nft),
+        total_nft,
+        owner: state.owner,
+        max_supply,
+        paused: state.paused,
+        pause_reason: state.pause_reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::hash_pair;
+    use crate::migrations::store_legacy_state;
+    use crate::state::Royalty;
+    use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        from_slice, ContractResult, Empty, OwnedDeps, Querier, QuerierResult, SystemResult,
+    };
+    use cosmwasm_storage::{bucket, singleton, singleton_read};
+    use cw721::{NftInfoResponse, OwnerOfResponse};
+    use cw_storage_plus::Map;
+    use std::marker::PhantomData;
+
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{coin, CosmosMsg, Decimal, ReplyOn, SubMsgExecutionResponse, SubMsgResult};
+
+    #[test]
+    fn buy_token() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            token: TokenContract::Existing {
+                address: "hope_token".to_string(),
+            },
+            nft: NftContract::Existing {
+                address: "hope_nft".to_string(),
+            },
+            maximum_nft: Uint128::new(1),
+            payment_tokens: vec![PaymentToken {
+                address: "token_contract".to_string(),
+                price: Uint128::new(1),
+            }],
+            native_prices: vec![],
+            max_supply: Uint128::new(2000),
+            payout_address: None,
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let info = mock_info("creator", &[]);
+        let address = String::from("token_address");
+        let message = ExecuteMsg::SetTokenAddress { address };
+        execute(deps.as_mut(), mock_env(), info, message).unwrap();
 
         let res = query_get_address(deps.as_ref()).unwrap();
         assert_eq!(res, "token_address");
 
-        let info = mock_info("sender", &[]);
-        let amount = 100;
+        let info = mock_info("creator", &[]);
+        let message = ExecuteMsg::SetExchange {
+            exchange: ExchangeConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::percent(150),
+                issuance_cap: Uint128::new(1000),
+                per_address_cap: Uint128::new(300),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), info, message).unwrap();
+
+        // 100 tokens at 1.5 ujuno each
+        let simulation =
+            query_simulate_buy_token(deps.as_ref(), Uint128::new(100), Some("sender".to_string()))
+                .unwrap();
+        assert_eq!(simulation.cost, coin(150, "ujuno"));
+        let simulation = query_simulate_buy_token(deps.as_ref(), Uint128::new(3), None).unwrap();
+        assert_eq!(simulation.cost, coin(5, "ujuno"));
+
+        let info = mock_info("sender", &[coin(149, "ujuno")]);
+        let amount = Uint128::new(100);
         let message = ExecuteMsg::BuyToken { amount };
-        let res = execute(deps.as_mut(), mock_env(), info, message).unwrap();
-        let message = res.messages[0].clone().msg;
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+        let info = mock_info("sender", &[coin(150, "uatom")]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+
+        let info = mock_info("sender", &[coin(160, "ujuno")]);
+        let res = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap();
+        let mint = res.messages[0].clone().msg;
         assert_eq!(
-            message,
+            mint,
             CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: String::from("token_address"),
                 msg: to_binary(&Cw20ExecuteMsg::Mint {
@@ -343,17 +2188,83 @@ mod tests {
                 funds: vec![]
             })
         );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender".to_string(),
+                amount: vec![coin(10, "ujuno")],
+            })
+        );
+
+        // per address and global caps
+        let info = mock_info("sender", &[coin(150, "ujuno")]);
+        execute(deps.as_mut(), mock_env(), info.clone(), message.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), message.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseCapExceeded {}));
+        let big = ExecuteMsg::BuyToken {
+            amount: Uint128::new(701),
+        };
+        let info = mock_info("whale", &[coin(2000, "ujuno")]);
+        let err = execute(deps.as_mut(), mock_env(), info, big).unwrap_err();
+        assert!(matches!(err, ContractError::IssuanceCapExceeded {}));
+
+        // amounts that overflow the issued totals hit the cap instead of panicking
+        let huge = ExecuteMsg::BuyToken {
+            amount: Uint128::MAX,
+        };
+        let info = mock_info("whale", &[coin(2000, "ujuno")]);
+        let err = execute(deps.as_mut(), mock_env(), info, huge).unwrap_err();
+        assert!(matches!(err, ContractError::IssuanceCapExceeded {}));
+        let err = query_simulate_buy_token(deps.as_ref(), Uint128::MAX, None).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::IssuanceCapExceeded {}.to_string())
+        );
+
+        // a zero price would hand out tokens for free
+        let message = ExecuteMsg::SetExchange {
+            exchange: ExchangeConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::zero(),
+                issuance_cap: Uint128::new(1000),
+                per_address_cap: Uint128::new(300),
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            message,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
     }
 
     #[test]
     fn receive() {
         let mut deps = mock_dependencies();
-        let instantiate_msg = InstantiateMsg {};
+        let instantiate_msg = InstantiateMsg {
+            token: TokenContract::Existing {
+                address: "hope_token".to_string(),
+            },
+            nft: NftContract::Existing {
+                address: "hope_nft".to_string(),
+            },
+            maximum_nft: Uint128::new(1),
+            payment_tokens: vec![PaymentToken {
+                address: "token_contract".to_string(),
+                price: Uint128::new(1),
+            }],
+            native_prices: vec![],
+            max_supply: Uint128::new(2000),
+            payout_address: None,
+        };
         let info = mock_info("creator", &[]);
         let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
         assert_eq!(0, res.messages.len());
 
-        let token_count = query_token_count(deps.as_ref(), "sender".to_string()).unwrap();
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
         assert_eq!(
             token_count,
             TokenCount {
@@ -388,7 +2299,10 @@ mod tests {
             external_link: Some("https://hope1".to_string()),
             description: Some("Galaxy NFT".to_string()),
             init_price: Some(Uint128::new(1)),
-            nft_addr: Some("Nft_address".to_string()),
+            nft_addr: Some("nft_contract".to_string()),
+            quantity: None,
+            proof: None,
+            allocation: None,
             royalties: Some(vec![Royalty {
                 address: "creator".to_string(),
                 royalty_rate: Decimal::from_atomics(3u64, 1).unwrap(),
@@ -398,7 +2312,7 @@ mod tests {
             name: mint_msg.clone().name,
             description: mint_msg.clone().description,
             external_link: mint_msg.clone().external_link,
-        
+
             royalties: mint_msg.clone().royalties,
             init_price: mint_msg.clone().init_price,
         };
@@ -437,7 +2351,7 @@ mod tests {
                 funds: vec![],
             })
         );
-        let token_count = query_token_count(deps.as_ref(), "sender".to_string()).unwrap();
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
         assert_eq!(
             token_count,
             TokenCount {
@@ -445,38 +2359,33 @@ mod tests {
                 owned_nft_number: Uint128::new(1)
             }
         );
-        for i in 0..4 {
+        for _ in 0..4 {
             let info = mock_info("token_contract", &[]);
             let mint_msg = HopeMintMsg {
                 name: Some("hope1".to_string()),
                 image_uri: Some("https://hope1".to_string()),
                 external_link: Some("https://hope1".to_string()),
                 description: Some("Galaxy NFT".to_string()),
-            
+
                 init_price: Some(Uint128::new(1)),
-                nft_addr: Some("Nft_address".to_string()),
+                nft_addr: Some("nft_contract".to_string()),
+                quantity: None,
+                proof: None,
+                allocation: None,
                 royalties: Some(vec![Royalty {
                     address: "creator".to_string(),
                     royalty_rate: Decimal::from_atomics(3u64, 1).unwrap(),
                 }]),
             };
-              Metadata {
-                name: mint_msg.clone().name,
-                description: mint_msg.clone().description,
-                external_link: mint_msg.clone().external_link,
-               
-                royalties: mint_msg.clone().royalties,
-                init_price: mint_msg.clone().init_price,
-            };
 
             let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
                 sender: "sender".to_string(),
                 amount: Uint128::new(1),
                 msg: to_binary(&mint_msg).unwrap(),
             });
-             execute(deps.as_mut(), mock_env(), info, message).unwrap();
+            execute(deps.as_mut(), mock_env(), info, message).unwrap();
         }
-        let token_count = query_token_count(deps.as_ref(), "sender".to_string()).unwrap();
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
         assert_eq!(
             token_count,
             TokenCount {
@@ -491,20 +2400,15 @@ mod tests {
             external_link: Some("https://hope1".to_string()),
             description: Some("Galaxy NFT".to_string()),
             init_price: Some(Uint128::new(1)),
-            nft_addr: Some("Nft_address".to_string()),
+            nft_addr: Some("nft_contract".to_string()),
+            quantity: None,
+            proof: None,
+            allocation: None,
             royalties: Some(vec![Royalty {
                 address: "creator".to_string(),
                 royalty_rate: Decimal::from_atomics(3u64, 1).unwrap(),
             }]),
         };
-        Metadata {
-            name: mint_msg.clone().name,
-            description: mint_msg.clone().description,
-            external_link: mint_msg.clone().external_link,
-        
-            royalties: mint_msg.clone().royalties,
-            init_price: mint_msg.clone().init_price,
-        };
 
         let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "sender1".to_string(),
@@ -512,7 +2416,7 @@ mod tests {
             msg: to_binary(&mint_msg).unwrap(),
         });
         execute(deps.as_mut(), mock_env(), info, message).unwrap();
-        let token_count = query_token_count(deps.as_ref(), "sender".to_string()).unwrap();
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
         assert_eq!(
             token_count,
             TokenCount {
@@ -528,22 +2432,17 @@ mod tests {
                 image_uri: Some("https://hope1".to_string()),
                 external_link: Some("https://hope1".to_string()),
                 description: Some("Galaxy NFT".to_string()),
-            
+
                 init_price: Some(Uint128::new(1)),
-                nft_addr: Some("Nft_address".to_string()),
+                nft_addr: Some("nft_contract".to_string()),
+                quantity: None,
+                proof: None,
+                allocation: None,
                 royalties: Some(vec![Royalty {
                     address: "creator".to_string(),
                     royalty_rate: Decimal::from_atomics(3u64, 1).unwrap(),
                 }]),
             };
-            Metadata {
-                name: mint_msg.clone().name,
-                description: mint_msg.clone().description,
-                external_link: mint_msg.clone().external_link,
-               
-                royalties: mint_msg.clone().royalties,
-                init_price: mint_msg.clone().init_price,
-            };
 
             let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
                 sender: i.to_string() + "sender",
@@ -553,10 +2452,19 @@ mod tests {
             execute(deps.as_mut(), mock_env(), info, message).unwrap();
         }
 
-        let users = query_get_users(deps.as_ref()).unwrap();
-        assert_eq!(users.len(), 1996);
+        assert_eq!(query_user_count(deps.as_ref()).unwrap(), 1996);
+        let users = query_get_users(deps.as_ref(), None, None).unwrap();
+        assert_eq!(users.len(), 10);
+        let users = query_get_users(deps.as_ref(), None, Some(100)).unwrap();
+        assert_eq!(users.len(), 30);
+        let next = query_get_users(deps.as_ref(), users.last().cloned(), Some(30)).unwrap();
+        assert_eq!(next.len(), 30);
+        assert!(next[0] > users[29]);
+        let users_info = query_users_info(deps.as_ref(), None, Some(2)).unwrap();
+        assert_eq!(users_info[0].address, users[0]);
+        assert_eq!(users_info[1].nft.len(), 1);
 
-        let token_count = query_token_count(deps.as_ref(), "2000sender".to_string()).unwrap();
+        let token_count = query_token_count(deps.as_ref(), "2000sender".to_string(), None).unwrap();
         assert_eq!(
             token_count,
             TokenCount {
@@ -565,10 +2473,2572 @@ mod tests {
             }
         );
 
-        let state = query_get_info(deps.as_ref()).unwrap();
-        assert_eq!(state,State{
-            owner:"creator".to_string(),
-            total_nft:Uint128::new(2000)
-        })
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert_eq!(
+            state,
+            StateResponse {
+                owner: Some("creator".to_string()),
+                total_nft: Uint128::new(2000),
+                max_supply: Uint128::new(2000),
+                remaining_supply: Uint128::zero(),
+                paused: false,
+                pause_reason: None,
+            }
+        )
+    }
+
+    fn setup_contract(deps: DepsMut, mint_price: Uint128) {
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            token: TokenContract::Existing {
+                address: "token_contract".to_string(),
+            },
+            nft: NftContract::Existing {
+                address: "nft_contract".to_string(),
+            },
+            maximum_nft: Uint128::new(5),
+            payment_tokens: vec![PaymentToken {
+                address: "token_contract".to_string(),
+                price: mint_price,
+            }],
+            native_prices: vec![],
+            max_supply: Uint128::new(2000),
+            payout_address: None,
+        };
+        instantiate(deps, mock_env(), info, msg).unwrap();
+    }
+
+    fn token_asset(address: &str) -> AssetInfo {
+        AssetInfo::Token {
+            contract_addr: address.to_string(),
+        }
+    }
+
+    fn simple_mint_msg() -> HopeMintMsg {
+        HopeMintMsg {
+            name: Some("hope1".to_string()),
+            image_uri: None,
+            external_link: None,
+            description: None,
+            init_price: None,
+            nft_addr: None,
+            royalties: None,
+            quantity: None,
+            proof: None,
+            allocation: None,
+        }
+    }
+
+    #[test]
+    fn receive_rejects_forged_token() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(1),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+
+        // a fake cw20 contract forwarding the hook
+        let info = mock_info("fake_token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidToken {}));
+
+        // the user calling the hook directly
+        let info = mock_info("sender", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, message).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidToken {}));
+
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
+        assert_eq!(
+            token_count,
+            TokenCount {
+                total_nft: Uint128::new(0),
+                owned_nft_number: Uint128::new(0)
+            }
+        );
+    }
+
+    #[test]
+    fn receive_enforces_mint_price() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(100));
+        assert_eq!(
+            query_mint_price(deps.as_ref(), token_asset("token_contract")).unwrap(),
+            Uint128::new(100)
+        );
+
+        // underpaying fails
+        let info = mock_info("token_contract", &[]);
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(99),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), message).unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+
+        // overpaying pays the owner the price and refunds the rest
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(150),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), message).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap()
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "sender".to_string(),
+                    amount: Uint128::new(50),
+                })
+                .unwrap()
+            })
+        );
+
+        // only the owner may change the price
+        let msg = ExecuteMsg::AddPaymentToken {
+            address: "token_contract".to_string(),
+            price: Uint128::new(10),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_mint_price(deps.as_ref(), token_asset("token_contract")).unwrap(),
+            Uint128::new(10)
+        );
+    }
+
+    #[test]
+    fn mint_with_native_coins() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(100));
+
+        let msg = ExecuteMsg::SetNativePrice {
+            denom: "ujuno".to_string(),
+            price: Uint128::new(20),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_native_prices(deps.as_ref()).unwrap(),
+            vec![coin(20, "ujuno")]
+        );
+        let msg = ExecuteMsg::SetNativePrice {
+            denom: "ujuno".to_string(),
+            price: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        // unknown denoms and mixed funds are rejected
+        let message = ExecuteMsg::Mint(simple_mint_msg());
+        let info = mock_info("sender", &[coin(20, "uatom")]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+        let info = mock_info("sender", &[coin(20, "ujuno"), coin(20, "uatom")]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+        let info = mock_info("sender", &[coin(19, "ujuno")]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+
+        let info = mock_info("sender", &[coin(25, "ujuno")]);
+        let res = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: vec![coin(20, "ujuno")],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender".to_string(),
+                amount: vec![coin(5, "ujuno")],
+            })
+        );
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
+        assert_eq!(
+            token_count,
+            TokenCount {
+                total_nft: Uint128::new(1),
+                owned_nft_number: Uint128::new(1)
+            }
+        );
+
+        // removed denoms are no longer accepted
+        let msg = ExecuteMsg::RemoveNativePrice {
+            denom: "ujuno".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let info = mock_info("sender", &[coin(20, "ujuno")]);
+        let err = execute(deps.as_mut(), mock_env(), info, message).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+    }
+
+    #[test]
+    fn multiple_payment_tokens() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(100));
+
+        let msg = ExecuteMsg::AddPaymentToken {
+            address: "stable_token".to_string(),
+            price: Uint128::new(5),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AddPaymentToken {
+            address: "free_token".to_string(),
+            price: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        // the price is looked up by the calling token
+        let info = mock_info("stable_token", &[]);
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(5),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "stable_token".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".to_string(),
+                    amount: Uint128::new(5),
+                })
+                .unwrap()
+            })
+        );
+        let info = mock_info("token_contract", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+
+        let tokens = query_payment_tokens(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                PaymentToken {
+                    address: "stable_token".to_string(),
+                    price: Uint128::new(5),
+                },
+                PaymentToken {
+                    address: "token_contract".to_string(),
+                    price: Uint128::new(100),
+                },
+            ]
+        );
+        let tokens =
+            query_payment_tokens(deps.as_ref(), Some("stable_token".to_string()), Some(1)).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].address, "token_contract");
+
+        let msg = ExecuteMsg::RemovePaymentToken {
+            address: "stable_token".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let info = mock_info("stable_token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, message).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidToken {}));
+        query_mint_price(deps.as_ref(), token_asset("stable_token")).unwrap_err();
+    }
+
+    #[test]
+    fn batch_mint() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+
+        let msg = ExecuteMsg::SetMaxPerTx {
+            amount: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+        assert_eq!(query_max_per_tx(deps.as_ref()).unwrap(), None);
+
+        let msg = ExecuteMsg::SetMaxPerTx {
+            amount: Uint128::new(3),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_max_per_tx(deps.as_ref()).unwrap(),
+            Some(Uint128::new(3))
+        );
+
+        let receive = |quantity: u32, amount: u128| {
+            let mut mint_msg = simple_mint_msg();
+            mint_msg.quantity = Some(quantity);
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "sender".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&mint_msg).unwrap(),
+            })
+        };
+        let info = mock_info("token_contract", &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(0, 10)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidQuantity {}));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(4, 40)).unwrap_err();
+        assert!(matches!(err, ContractError::MaxPerTxExceeded {}));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(3, 29)).unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), receive(3, 30)).unwrap();
+        // payout plus three mints
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".to_string(),
+                    amount: Uint128::new(30),
+                })
+                .unwrap()
+            })
+        );
+        let user_info = query_user_info(deps.as_ref(), "sender".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(user_info.nft, vec!["Hope.0", "Hope.1", "Hope.2"]);
+
+        // the per wallet limit of five applies to the whole batch
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(3, 30)).unwrap_err();
+        assert!(matches!(err, ContractError::MintExceeded {}));
+        let token_count = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
+        assert_eq!(
+            token_count,
+            TokenCount {
+                total_nft: Uint128::new(3),
+                owned_nft_number: Uint128::new(3)
+            }
+        );
+        execute(deps.as_mut(), mock_env(), info, receive(2, 20)).unwrap();
+    }
+
+    #[test]
+    fn max_supply() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let info = mock_info("token_contract", &[]);
+        let mut mint_msg = simple_mint_msg();
+        mint_msg.quantity = Some(2);
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(2),
+            msg: to_binary(&mint_msg).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), info.clone(), message.clone()).unwrap();
+
+        // supply can only go down, and never below what is already minted
+        let creator = mock_info("creator", &[]);
+        let set_supply = |amount: u128| ExecuteMsg::SetMaxSupply {
+            amount: Uint128::new(amount),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), set_supply(3)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err =
+            execute(deps.as_mut(), mock_env(), creator.clone(), set_supply(2001)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSupply {}));
+        let err = execute(deps.as_mut(), mock_env(), creator.clone(), set_supply(1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSupply {}));
+        execute(deps.as_mut(), mock_env(), creator, set_supply(3)).unwrap();
+
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert_eq!(
+            state,
+            StateResponse {
+                owner: Some("creator".to_string()),
+                total_nft: Uint128::new(2),
+                max_supply: Uint128::new(3),
+                remaining_supply: Uint128::new(1),
+                paused: false,
+                pause_reason: None,
+            }
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), info, message).unwrap_err();
+        assert!(matches!(err, ContractError::MintEnded {}));
+    }
+
+    #[test]
+    fn sale_phases() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+
+        let now = mock_env().block.time;
+        let env_at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = now.plus_seconds(seconds);
+            env
+        };
+        let presale = Phase {
+            name: "presale".to_string(),
+            start: now.plus_seconds(100),
+            end: Some(now.plus_seconds(200)),
+            prices: vec![Asset {
+                info: token_asset("token_contract"),
+                amount: Uint128::new(5),
+            }],
+            per_wallet_limit: Some(Uint128::new(1)),
+            eligibility: Eligibility::Allowlist {
+                addresses: vec!["sender".to_string()],
+            },
+        };
+        let public = Phase {
+            name: "public".to_string(),
+            start: now.plus_seconds(200),
+            end: Some(now.plus_seconds(300)),
+            prices: vec![],
+            per_wallet_limit: None,
+            eligibility: Eligibility::Everyone {},
+        };
+
+        // overlapping phases are rejected
+        let mut overlapping = public.clone();
+        overlapping.start = now.plus_seconds(150);
+        let msg = ExecuteMsg::SetPhases {
+            phases: vec![presale.clone(), overlapping],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPhases {}));
+
+        let msg = ExecuteMsg::SetPhases {
+            phases: vec![presale.clone(), public.clone()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_phases(deps.as_ref()).unwrap(),
+            vec![presale.clone(), public.clone()]
+        );
+        assert_eq!(query_active_phase(deps.as_ref(), env_at(0)).unwrap(), None);
+        assert_eq!(
+            query_active_phase(deps.as_ref(), env_at(150)).unwrap(),
+            Some(presale)
+        );
+
+        let receive = |sender: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&simple_mint_msg()).unwrap(),
+            })
+        };
+        let info = mock_info("token_contract", &[]);
+
+        let err =
+            execute(deps.as_mut(), env_at(0), info.clone(), receive("sender", 5)).unwrap_err();
+        assert!(matches!(err, ContractError::PhaseNotStarted { .. }));
+
+        // presale is limited to the allowlist at a discounted price
+        let err = execute(
+            deps.as_mut(),
+            env_at(150),
+            info.clone(),
+            receive("other", 5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        let res = execute(
+            deps.as_mut(),
+            env_at(150),
+            info.clone(),
+            receive("sender", 5),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let err = execute(
+            deps.as_mut(),
+            env_at(150),
+            info.clone(),
+            receive("sender", 5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintExceeded {}));
+
+        // public sale falls back to the registry price
+        let err = execute(
+            deps.as_mut(),
+            env_at(250),
+            info.clone(),
+            receive("other", 5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+        execute(
+            deps.as_mut(),
+            env_at(250),
+            info.clone(),
+            receive("other", 10),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env_at(250),
+            info.clone(),
+            receive("sender", 10),
+        )
+        .unwrap();
+
+        let err = execute(deps.as_mut(), env_at(300), info, receive("other", 10)).unwrap_err();
+        assert!(matches!(err, ContractError::PhaseEnded { .. }));
+    }
+
+    #[test]
+    fn merkle_allowlist() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+
+        // sender may mint under the phase limit, other has an allocation of two
+        let sender_leaf = leaf_hash("sender", None);
+        let other_leaf = leaf_hash("other", Some("2"));
+        let third_leaf = leaf_hash("third", None);
+        let root = hash_pair(&hash_pair(&sender_leaf, &other_leaf), &third_leaf);
+
+        let now = mock_env().block.time;
+        let presale = Phase {
+            name: "presale".to_string(),
+            start: now,
+            end: None,
+            prices: vec![],
+            per_wallet_limit: Some(Uint128::new(1)),
+            eligibility: Eligibility::Everyone {},
+        };
+        let creator = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetPhases {
+            phases: vec![presale],
+        };
+        execute(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetMerkleRoot {
+            phase: "presale".to_string(),
+            root: "not hex".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleRoot {}));
+        let msg = ExecuteMsg::SetMerkleRoot {
+            phase: "presale".to_string(),
+            root: hex::encode(root),
+        };
+        execute(deps.as_mut(), mock_env(), creator, msg).unwrap();
+
+        let receive = |sender: &str, proof: Vec<Hash>, allocation: Option<u128>| {
+            let mut mint_msg = simple_mint_msg();
+            mint_msg.proof = Some(proof.iter().map(hex::encode).collect());
+            mint_msg.allocation = allocation.map(Uint128::new);
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(10),
+                msg: to_binary(&mint_msg).unwrap(),
+            })
+        };
+        let info = mock_info("token_contract", &[]);
+
+        let sender_proof = vec![other_leaf, third_leaf];
+        let other_proof = vec![sender_leaf, third_leaf];
+
+        // a proof for someone else does not work
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("third", sender_proof.clone(), None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        // nor does claiming a different allocation
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("other", other_proof.clone(), Some(3)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        let msg = receive("sender", sender_proof, None);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MintExceeded {}));
+
+        let msg = receive("other", other_proof, Some(2));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::MintExceeded {}));
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let msg = ExecuteMsg::Pause {
+            reason: Some("metadata issue".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert!(state.paused);
+        assert_eq!(state.pause_reason, Some("metadata issue".to_string()));
+
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(1),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let info = mock_info("token_contract", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), message.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let buy = ExecuteMsg::BuyToken {
+            amount: Uint128::new(100),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("sender", &[]), buy).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+
+        let msg = ExecuteMsg::Unpause {};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert!(!state.paused);
+        assert_eq!(state.pause_reason, None);
+        execute(deps.as_mut(), mock_env(), info, message).unwrap();
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let creator = mock_info("creator", &[]);
+        let new_owner = mock_info("new_owner", &[]);
+        let propose = ExecuteMsg::ProposeNewOwner {
+            owner: "new_owner".to_string(),
+            expiry: Some(Expiration::AtHeight(mock_env().block.height + 10)),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            new_owner.clone(),
+            propose.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            new_owner.clone(),
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingOwner {}));
+
+        // a cancelled proposal can't be accepted
+        execute(deps.as_mut(), mock_env(), creator.clone(), propose.clone()).unwrap();
+        let msg = ExecuteMsg::CancelOwnershipProposal {};
+        execute(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::AcceptOwnership {};
+        let err = execute(deps.as_mut(), mock_env(), new_owner.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingOwner {}));
+
+        // nor an expired one
+        execute(deps.as_mut(), mock_env(), creator.clone(), propose.clone()).unwrap();
+        let mut late = mock_env();
+        late.block.height += 10;
+        let msg = ExecuteMsg::AcceptOwnership {};
+        let err = execute(deps.as_mut(), late, new_owner.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipProposalExpired {}));
+
+        let msg = ExecuteMsg::AcceptOwnership {};
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), new_owner.clone(), msg).unwrap();
+        assert_eq!(
+            query_ownership(deps.as_ref()).unwrap(),
+            OwnershipResponse {
+                owner: Some("new_owner".to_string()),
+                pending_owner: None,
+            }
+        );
+
+        // the old owner lost its rights
+        let msg = ExecuteMsg::SetMaximumNft {
+            amount: Uint128::new(10),
+        };
+        let err = execute(deps.as_mut(), mock_env(), creator, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), new_owner.clone(), msg.clone()).unwrap();
+
+        // after renouncing nobody can change the config but proceeds still flow
+        let renounce = ExecuteMsg::RenounceOwnership {};
+        execute(deps.as_mut(), mock_env(), new_owner.clone(), renounce).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), new_owner, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        assert_eq!(query_ownership(deps.as_ref()).unwrap().owner, None);
+
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(1),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let info = mock_info("token_contract", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, message).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "new_owner".to_string(),
+                    amount: Uint128::new(1),
+                })
+                .unwrap()
+            })
+        );
+    }
+
+    #[test]
+    fn roles() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let creator = mock_info("creator", &[]);
+        let admin = mock_info("admin", &[]);
+        let operator = mock_info("operator", &[]);
+        let treasurer = mock_info("treasurer", &[]);
+        let grant = |address: &str, role: Role| ExecuteMsg::GrantRole {
+            address: address.to_string(),
+            role,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            operator.clone(),
+            grant("operator", Role::Operator),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            creator,
+            grant("admin", Role::Admin),
+        )
+        .unwrap();
+        // admins manage the other roles
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            grant("operator", Role::Operator),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            grant("treasurer", Role::Treasurer),
+        )
+        .unwrap();
+        assert_eq!(
+            query_roles(deps.as_ref()).unwrap(),
+            vec![
+                RoleInfo {
+                    address: "admin".to_string(),
+                    roles: vec![Role::Admin],
+                },
+                RoleInfo {
+                    address: "operator".to_string(),
+                    roles: vec![Role::Operator],
+                },
+                RoleInfo {
+                    address: "treasurer".to_string(),
+                    roles: vec![Role::Treasurer],
+                },
+            ]
+        );
+
+        // each role only reaches its own messages
+        let set_max = ExecuteMsg::SetMaximumNft {
+            amount: Uint128::new(10),
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), operator.clone(), set_max.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), admin.clone(), set_max).unwrap();
+
+        let pause = ExecuteMsg::Pause { reason: None };
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), pause.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), operator.clone(), pause).unwrap();
+        let airdrop = ExecuteMsg::Airdrop {
+            recipients: vec!["sender".to_string(), "sender1".to_string()],
+            msg: simple_mint_msg(),
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), operator.clone(), airdrop.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            operator.clone(),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), airdrop.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), operator.clone(), airdrop).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let token_count = query_token_count(deps.as_ref(), "sender1".to_string(), None).unwrap();
+        assert_eq!(
+            token_count,
+            TokenCount {
+                total_nft: Uint128::new(2),
+                owned_nft_number: Uint128::new(1)
+            }
+        );
+
+        let withdraw = ExecuteMsg::Withdraw {
+            asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "ujuno".to_string(),
+                },
+                amount: Uint128::new(100),
+            },
+            recipient: Some("vault".to_string()),
+        };
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), treasurer, withdraw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "vault".to_string(),
+                amount: vec![coin(100, "ujuno")],
+            })
+        );
+
+        let revoke = ExecuteMsg::RevokeRole {
+            address: "operator".to_string(),
+            role: Role::Operator,
+        };
+        execute(deps.as_mut(), mock_env(), admin, revoke).unwrap();
+        let pause = ExecuteMsg::Pause { reason: None };
+        let err = execute(deps.as_mut(), mock_env(), operator, pause).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        assert_eq!(query_roles(deps.as_ref()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn redeem() {
+        let mut deps = mock_dependencies_with_balance(&[coin(500, "ujuno")]);
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let msg = ExecuteMsg::SetRedemption {
+            redemption: RedemptionConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::percent(200),
+                spread: Decimal::percent(10),
+                burn: true,
+                period: 100,
+                period_limit: Uint128::new(300),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let redeem = |amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "sender".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Redeem {}).unwrap(),
+            })
+        };
+
+        // other tokens can't be sold back
+        let info = mock_info("other_token", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, redeem(100)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidToken {}));
+
+        // 100 tokens at 2 ujuno minus a 10% spread
+        let info = mock_info("token_contract", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), redeem(100)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sender".to_string(),
+                amount: vec![coin(180, "ujuno")],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(100)
+                })
+                .unwrap(),
+            })
+        );
+
+        let treasury = query_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.reserves, vec![coin(500, "ujuno")]);
+        assert_eq!(treasury.redeemed_in_period, Uint128::new(100));
+        assert_eq!(treasury.remaining_in_period, Uint128::new(200));
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), redeem(201)).unwrap_err();
+        assert!(matches!(err, ContractError::RedemptionLimitExceeded {}));
+
+        // a price that overflows the payout fails instead of panicking
+        let msg = ExecuteMsg::SetRedemption {
+            redemption: RedemptionConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::from_ratio(10u128.pow(20), 1u128),
+                spread: Decimal::zero(),
+                burn: true,
+                period: 100,
+                period_limit: Uint128::MAX,
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            redeem(10u128.pow(19)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Std(StdError::ConversionOverflow { .. })
+        ));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), redeem(u128::MAX)).unwrap_err();
+        assert!(matches!(err, ContractError::RedemptionLimitExceeded {}));
+
+        // the period has to be at least a second
+        let msg = ExecuteMsg::SetRedemption {
+            redemption: RedemptionConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::percent(200),
+                spread: Decimal::percent(10),
+                burn: true,
+                period: 0,
+                period_limit: Uint128::new(300),
+            },
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+        let msg = ExecuteMsg::SetRedemption {
+            redemption: RedemptionConfig {
+                denom: "ujuno".to_string(),
+                price: Decimal::percent(200),
+                spread: Decimal::percent(10),
+                burn: true,
+                period: 100,
+                period_limit: Uint128::new(300),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // redemptions stop while the contract is paused
+        let msg = ExecuteMsg::Pause { reason: None };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), redeem(100)).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+
+        // the limit resets with the next period, reserves still apply
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(100);
+        let err = execute(deps.as_mut(), later.clone(), info.clone(), redeem(300)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserves {}));
+        execute(deps.as_mut(), later, info, redeem(200)).unwrap();
+    }
+
+    #[test]
+    fn instantiate_with_config() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            token: TokenContract::Existing {
+                address: "token_contract".to_string(),
+            },
+            nft: NftContract::Existing {
+                address: "nft_contract".to_string(),
+            },
+            maximum_nft: Uint128::new(3),
+            payment_tokens: vec![],
+            native_prices: vec![coin(50, "ujuno")],
+            max_supply: Uint128::new(100),
+            payout_address: Some("treasury".to_string()),
+        };
+
+        let mut invalid = msg.clone();
+        invalid.maximum_nft = Uint128::new(101);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            invalid,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+        let mut invalid = msg.clone();
+        invalid.native_prices = vec![];
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            invalid,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+        let mut invalid = msg.clone();
+        invalid.native_prices = vec![coin(0, "ujuno")];
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            invalid,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(query_get_address(deps.as_ref()).unwrap(), "token_contract");
+        assert_eq!(query_maximum_nft(deps.as_ref()).unwrap(), Uint128::new(3));
+
+        // minting works without any follow-up configuration
+        let info = mock_info("sender", &[coin(50, "ujuno")]);
+        let msg = ExecuteMsg::Mint(simple_mint_msg());
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![coin(50, "ujuno")],
+            })
+        );
+    }
+
+    #[test]
+    fn factory_instantiate() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            token: TokenContract::Instantiate {
+                code_id: 1,
+                label: "hope token".to_string(),
+                name: "Hope".to_string(),
+                symbol: "HOPE".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                cap: None,
+                mint_price: Some(Uint128::new(20)),
+            },
+            nft: NftContract::Instantiate {
+                code_id: 2,
+                label: "hope nft".to_string(),
+                name: "Hope NFT".to_string(),
+                symbol: "HOPENFT".to_string(),
+            },
+            maximum_nft: Uint128::new(3),
+            payment_tokens: vec![],
+            native_prices: vec![],
+            max_supply: Uint128::new(100),
+            payout_address: None,
+        };
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].id, INSTANTIATE_TOKEN_REPLY_ID);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: Some("creator".to_string()),
+                code_id: 1,
+                msg: to_binary(&Cw20InstantiateMsg {
+                    name: "Hope".to_string(),
+                    symbol: "HOPE".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                })
+                .unwrap(),
+                funds: vec![],
+                label: "hope token".to_string(),
+            })
+        );
+        assert_eq!(res.messages[1].id, INSTANTIATE_NFT_REPLY_ID);
+        assert!(query_get_address(deps.as_ref()).is_err());
+
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(INSTANTIATE_TOKEN_REPLY_ID, "new_token"),
+        )
+        .unwrap();
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            instantiate_reply(INSTANTIATE_NFT_REPLY_ID, "new_nft"),
+        )
+        .unwrap();
+        assert_eq!(query_get_address(deps.as_ref()).unwrap(), "new_token");
+        assert_eq!(read_nft_address(&deps.storage).unwrap(), "new_nft");
+
+        // the new token is accepted as payment right away
+        assert_eq!(
+            query_mint_price(deps.as_ref(), token_asset("new_token")).unwrap(),
+            Uint128::new(20)
+        );
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(20),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("new_token", &[]), msg).unwrap();
+
+        let err = reply(deps.as_mut(), mock_env(), instantiate_reply(7, "other")).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReply { id: 7 }));
+    }
+
+    // reply carrying a protobuf encoded MsgInstantiateContractResponse
+    fn instantiate_reply(id: u64, address: &str) -> Reply {
+        let mut data = vec![0x0a, address.len() as u8];
+        data.extend_from_slice(address.as_bytes());
+        Reply {
+            id,
+            result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                events: vec![],
+                data: Some(Binary::from(data)),
+            }),
+        }
+    }
+
+    #[test]
+    fn mint_confirmation() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+
+        let mut mint_msg = simple_mint_msg();
+        mint_msg.quantity = Some(2);
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(20),
+            msg: to_binary(&mint_msg).unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("token_contract", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(res.messages[1].id, MINT_REPLY_ID_OFFSET);
+        assert_eq!(res.messages[1].reply_on, ReplyOn::Success);
+        assert_eq!(res.messages[2].id, MINT_REPLY_ID_OFFSET + 1);
+        assert_eq!(read_mint_record(&deps.storage, "Hope.0"), None);
+
+        let confirmation = Reply {
+            id: MINT_REPLY_ID_OFFSET + 1,
+            result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), confirmation.clone()).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("mint_confirmed")
+                .add_attribute("token_id", "Hope.1")
+                .add_attribute("minter", "sender")
+                .add_attribute("height", mock_env().block.height.to_string())]
+        );
+        assert_eq!(
+            read_mint_record(&deps.storage, "Hope.1"),
+            Some(MintRecord {
+                token_id: "Hope.1".to_string(),
+                minter: Addr::unchecked("sender"),
+                height: mock_env().block.height,
+                time: Some(mock_env().block.time),
+                payment: Some(Asset {
+                    info: token_asset("token_contract"),
+                    amount: Uint128::new(10),
+                }),
+                phase: None,
+            })
+        );
+
+        let minted: Vec<String> = read_mint_history(
+            &deps.storage,
+            Some(Addr::unchecked("sender")),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|record| record.token_id)
+        .collect();
+        assert_eq!(minted, vec!["Hope.1".to_string()]);
+
+        // a reply is only accepted once
+        let err = reply(deps.as_mut(), mock_env(), confirmation).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReply { .. }));
+    }
+
+    #[test]
+    fn mint_history() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+        let now = mock_env().block.time;
+        let env_at = |seconds: u64| {
+            let mut env = mock_env();
+            env.block.time = now.plus_seconds(seconds);
+            env
+        };
+        let presale = Phase {
+            name: "presale".to_string(),
+            start: now,
+            end: Some(now.plus_seconds(100)),
+            prices: vec![Asset {
+                info: token_asset("token_contract"),
+                amount: Uint128::new(5),
+            }],
+            per_wallet_limit: None,
+            eligibility: Eligibility::Everyone {},
+        };
+        let public = Phase {
+            name: "public".to_string(),
+            start: now.plus_seconds(100),
+            end: None,
+            prices: vec![],
+            per_wallet_limit: None,
+            eligibility: Eligibility::Everyone {},
+        };
+        let msg = ExecuteMsg::SetPhases {
+            phases: vec![presale, public],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // a record written before the ledger kept provenance
+        let legacy = MintRecord {
+            token_id: "Legacy.0".to_string(),
+            minter: Addr::unchecked("sender"),
+            height: 1,
+            time: None,
+            payment: None,
+            phase: None,
+        };
+        store_mint_record(&mut deps.storage, &legacy).unwrap();
+
+        // Hope.0 in the presale, Hope.1 and Hope.2 in the public sale
+        let info = mock_info("token_contract", &[]);
+        for (seconds, sender, amount) in [(0, "sender", 5), (100, "other", 10), (200, "sender", 10)]
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&simple_mint_msg()).unwrap(),
+            });
+            let res = execute(deps.as_mut(), env_at(seconds), info.clone(), msg).unwrap();
+            let confirmation = Reply {
+                id: res.messages.last().unwrap().id,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            };
+            reply(deps.as_mut(), env_at(seconds), confirmation).unwrap();
+        }
+
+        let record = query_mint_record(deps.as_ref(), "Hope.0".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            record,
+            MintRecord {
+                token_id: "Hope.0".to_string(),
+                minter: Addr::unchecked("sender"),
+                height: mock_env().block.height,
+                time: Some(now),
+                payment: Some(Asset {
+                    info: token_asset("token_contract"),
+                    amount: Uint128::new(5),
+                }),
+                phase: Some("presale".to_string()),
+            }
+        );
+        let record = query_mint_record(deps.as_ref(), "Hope.1".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.phase, Some("public".to_string()));
+        assert_eq!(record.payment.unwrap().amount, Uint128::new(10));
+        assert_eq!(
+            query_mint_record(deps.as_ref(), "Hope.3".to_string()).unwrap(),
+            None
+        );
+
+        let token_ids = |records: Vec<MintRecord>| -> Vec<String> {
+            records.into_iter().map(|record| record.token_id).collect()
+        };
+        let history = |minter: Option<&str>,
+                       start_time: Option<u64>,
+                       end_time: Option<u64>,
+                       start_after: Option<&str>,
+                       limit: Option<u32>| {
+            token_ids(
+                query_mint_history(
+                    deps.as_ref(),
+                    minter.map(str::to_string),
+                    start_time.map(|seconds| now.plus_seconds(seconds)),
+                    end_time.map(|seconds| now.plus_seconds(seconds)),
+                    start_after.map(str::to_string),
+                    limit,
+                )
+                .unwrap(),
+            )
+        };
+        // records without a time come first
+        assert_eq!(
+            history(None, None, None, None, None),
+            vec!["Legacy.0", "Hope.0", "Hope.1", "Hope.2"]
+        );
+        assert_eq!(
+            history(Some("sender"), None, None, None, None),
+            vec!["Legacy.0", "Hope.0", "Hope.2"]
+        );
+        assert_eq!(
+            history(Some("other"), None, None, None, None),
+            vec!["Hope.1"]
+        );
+        assert_eq!(
+            history(Some("sender"), None, None, Some("Hope.0"), Some(1)),
+            vec!["Hope.2"]
+        );
+        assert_eq!(
+            history(None, None, None, Some("Hope.0"), Some(1)),
+            vec!["Hope.1"]
+        );
+        // start_time is inclusive and end_time exclusive
+        assert_eq!(
+            history(None, Some(100), None, None, None),
+            vec!["Hope.1", "Hope.2"]
+        );
+        assert_eq!(
+            history(None, Some(100), None, Some("Hope.0"), None),
+            vec!["Hope.1", "Hope.2"]
+        );
+        assert_eq!(
+            history(None, Some(100), None, Some("Hope.1"), None),
+            vec!["Hope.2"]
+        );
+        assert_eq!(
+            history(None, Some(0), Some(200), None, None),
+            vec!["Hope.0", "Hope.1"]
+        );
+        assert_eq!(
+            history(Some("sender"), Some(100), Some(300), None, None),
+            vec!["Hope.2"]
+        );
+        assert_eq!(
+            history(None, Some(300), None, None, None),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn migrate_versions() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap();
+
+        // 0.12 ledger records only had the minter index
+        let record = MintRecord {
+            token_id: "Hope.0".to_string(),
+            minter: Addr::unchecked("sender"),
+            height: 1,
+            time: Some(mock_env().block.time),
+            payment: None,
+            phase: None,
+        };
+        Map::<&str, MintRecord>::new("MintLedger")
+            .save(&mut deps.storage, "Hope.0", &record)
+            .unwrap();
+        // along with an entry of the dropped minter index
+        let minter_index = Map::<(&Addr, &str), u32>::new("MintLedger__minter");
+        let minter = Addr::unchecked("sender");
+        minter_index
+            .save(&mut deps.storage, (&minter, "Hope.0"), &0)
+            .unwrap();
+        let history = |deps: Deps| {
+            query_mint_history(
+                deps,
+                Some("sender".to_string()),
+                Some(mock_env().block.time),
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+        assert_eq!(history(deps.as_ref()), vec![]);
+        // 0.12 collections did not reserve their prefix
+        store_collection(
+            &mut deps.storage,
+            &Collection {
+                nft_address: Addr::unchecked("pets"),
+                token_prefix: "Pet".to_string(),
+                max_supply: Uint128::new(3),
+                total_nft: Uint128::zero(),
+                prices: vec![],
+                per_wallet_limit: Uint128::new(1),
+            },
+        )
+        .unwrap();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.12.0").unwrap();
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(history(deps.as_ref()), vec![record]);
+        assert_eq!(
+            minter_index
+                .may_load(&deps.storage, (&minter, "Hope.0"))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            read_token_prefix(&deps.storage, "Pet").unwrap(),
+            Some(Addr::unchecked("pets"))
+        );
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CannotDowngrade { .. }));
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrate { .. }));
+    }
+
+    #[test]
+    fn migrate_legacy_deployment() {
+        let legacy_deployment = || {
+            let mut deps = mock_dependencies();
+            store_legacy_state(&mut deps.storage, Uint128::new(7), "creator");
+            singleton(&mut deps.storage, b"token_address")
+                .save(&Addr::unchecked("token_contract"))
+                .unwrap();
+            singleton(&mut deps.storage, b"nft_address")
+                .save(&Addr::unchecked("nft_contract"))
+                .unwrap();
+            singleton(&mut deps.storage, b"NFTMaximum")
+                .save(&Uint128::new(5))
+                .unwrap();
+            deps
+        };
+
+        // the legacy token can't stay unpriced
+        let mut deps = legacy_deployment();
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        let mut deps = legacy_deployment();
+        assert!(query_get_info(deps.as_ref(), None).is_err());
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: Some(Uint128::new(5)),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1].value, "0.0.0");
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert_eq!(state.total_nft, Uint128::new(7));
+        assert_eq!(state.owner, Some("creator".to_string()));
+        assert_eq!(state.max_supply, Uint128::new(2000));
+        assert_eq!(state.remaining_supply, Uint128::new(1993));
+        assert!(!state.paused);
+        assert_eq!(
+            cw2::get_contract_version(&deps.storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+
+        // minting through the legacy token keeps working
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(5),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        let info = mock_info("token_contract", &[]);
+        execute(deps.as_mut(), mock_env(), info, message).unwrap();
+        assert_eq!(
+            query_get_info(deps.as_ref(), None).unwrap().total_nft,
+            Uint128::new(8)
+        );
+    }
+
+    #[test]
+    fn migrate_storage_to_storage_plus() {
+        let mut deps = mock_dependencies();
+        let state = State {
+            total_nft: Uint128::new(3),
+            owner: Some("creator".to_string()),
+            max_supply: Uint128::new(100),
+            paused: false,
+            pause_reason: None,
+        };
+        let minter = Addr::unchecked("minter");
+        let whale = Addr::unchecked("whale");
+
+        // data as written by 0.10
+        singleton(&mut deps.storage, b"config")
+            .save(&state)
+            .unwrap();
+        singleton(&mut deps.storage, b"token_address")
+            .save(&Addr::unchecked("token_contract"))
+            .unwrap();
+        singleton(&mut deps.storage, b"NFTMaximum")
+            .save(&Uint128::new(5))
+            .unwrap();
+        for (user, tokens) in [
+            (&minter, vec!["Hope.0"]),
+            (&whale, vec!["Hope.1", "Hope.2"]),
+        ] {
+            let user_info = UserInfo {
+                address: user.to_string(),
+                nft: tokens.iter().map(|token| token.to_string()).collect(),
+            };
+            bucket(&mut deps.storage, b"User")
+                .save(user.as_bytes(), &user_info)
+                .unwrap();
+            bucket(&mut deps.storage, b"TokenCount")
+                .save(user.as_bytes(), &Uint128::from(tokens.len() as u128))
+                .unwrap();
+        }
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.10.0").unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                legacy_mint_price: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(CONFIG.load(&deps.storage).unwrap(), state);
+        assert!(singleton_read::<State>(&deps.storage, b"config")
+            .may_load()
+            .unwrap()
+            .is_none());
+        assert_eq!(query_get_address(deps.as_ref()).unwrap(), "token_contract");
+        assert_eq!(query_maximum_nft(deps.as_ref()).unwrap(), Uint128::new(5));
+        assert_eq!(
+            read_token_count(&deps.storage, &whale),
+            Some(Uint128::new(2))
+        );
+        assert_eq!(
+            read_user_info(&deps.storage, &minter).unwrap().nft,
+            vec!["Hope.0".to_string()]
+        );
+        assert_eq!(
+            read_users(&deps.storage, None, None).unwrap(),
+            vec!["minter".to_string(), "whale".to_string()]
+        );
+        assert_eq!(query_user_count(deps.as_ref()).unwrap(), 2);
+        let top = query_top_minters(deps.as_ref(), None).unwrap();
+        assert_eq!(top[0].address, "whale");
+        assert_eq!(top[1].address, "minter");
+        let top = query_top_minters(deps.as_ref(), Some(1)).unwrap();
+        assert_eq!(top.len(), 1);
+    }
+
+    #[test]
+    fn collections() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+
+        let set_collection = ExecuteMsg::SetCollection {
+            nft_address: "pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(3),
+            prices: vec![Asset {
+                info: token_asset("token_contract"),
+                amount: Uint128::new(25),
+            }],
+            per_wallet_limit: Uint128::new(2),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            set_collection.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), set_collection).unwrap();
+
+        // the prefix of every collection has to be unique
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "more_pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(3),
+            prices: vec![],
+            per_wallet_limit: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        let receive = |sender: &str, amount: u128, nft_addr: &str, quantity: u32| {
+            let mut mint_msg = simple_mint_msg();
+            mint_msg.nft_addr = Some(nft_addr.to_string());
+            mint_msg.quantity = Some(quantity);
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&mint_msg).unwrap(),
+            })
+        };
+        let info = mock_info("token_contract", &[]);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("sender", 25, "cats", 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownCollection {}));
+
+        // the collection price replaces the registry price
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("sender", 49, "pets", 2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Notenough {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("sender", 50, "pets", 2),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "pets".to_string(),
+                msg: to_binary(&Cw721BaseExecuteMsg::Mint(MintMsg {
+                    token_id: "Pet.0".to_string(),
+                    owner: "sender".to_string(),
+                    token_uri: None,
+                    extension: Metadata {
+                        name: Some("hope1".to_string()),
+                        description: None,
+                        external_link: None,
+                        royalties: None,
+                        init_price: None,
+                    },
+                }))
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // limits and counters are kept per collection
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("sender", 25, "pets", 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintExceeded {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("other", 50, "pets", 2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintEnded {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            receive("sender", 10, "nft_contract", 1),
+        )
+        .unwrap();
+
+        let pets = query_token_count(
+            deps.as_ref(),
+            "sender".to_string(),
+            Some("pets".to_string()),
+        )
+        .unwrap();
+        assert_eq!(pets.owned_nft_number, Uint128::new(2));
+        assert_eq!(pets.total_nft, Uint128::new(2));
+        let hope = query_token_count(deps.as_ref(), "sender".to_string(), None).unwrap();
+        assert_eq!(hope.owned_nft_number, Uint128::new(1));
+        let state = query_get_info(deps.as_ref(), Some("pets".to_string())).unwrap();
+        assert_eq!(state.max_supply, Uint128::new(3));
+        assert_eq!(state.remaining_supply, Uint128::new(1));
+        let state = query_get_info(deps.as_ref(), None).unwrap();
+        assert_eq!(state.total_nft, Uint128::new(1));
+        assert_eq!(
+            read_user_info(&deps.storage, &Addr::unchecked("sender"))
+                .unwrap()
+                .nft,
+            vec![
+                "Pet.0".to_string(),
+                "Pet.1".to_string(),
+                "Hope.0".to_string()
+            ]
+        );
+        let collections = query_collections(deps.as_ref(), None, None).unwrap();
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].total_nft, Uint128::new(2));
+
+        // a registered collection can't become the default one
+        let msg = ExecuteMsg::SetNftAddress {
+            address: "pets".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        // a removed collection stops minting
+        let msg = ExecuteMsg::RemoveCollection {
+            nft_address: "pets".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            query_collections(deps.as_ref(), None, None).unwrap(),
+            vec![]
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("token_contract", &[]),
+            receive("other", 25, "pets", 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownCollection {}));
+
+        // its prefix stays reserved for it
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "toys".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(4),
+            prices: vec![],
+            per_wallet_limit: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        // registering it again continues its token numbering
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(1),
+            prices: vec![],
+            per_wallet_limit: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSupply {}));
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(4),
+            prices: vec![],
+            per_wallet_limit: Uint128::new(1),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("token_contract", &[]),
+            receive("other", 10, "pets", 1),
+        )
+        .unwrap();
+        assert_eq!(
+            read_user_info(&deps.storage, &Addr::unchecked("other"))
+                .unwrap()
+                .nft,
+            vec!["Pet.2".to_string()]
+        );
+    }
+
+    // answers cw721 queries from a fixed set of (contract, token id, metadata)
+    struct Cw721Querier {
+        base: MockQuerier,
+        tokens: Vec<(String, String, Metadata)>,
+    }
+
+    impl Querier for Cw721Querier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            let (contract, msg) = match &request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => (contract_addr, msg),
+                _ => return self.base.handle_query(&request),
+            };
+            let tokens = self
+                .tokens
+                .iter()
+                .filter(|(token_contract, _, _)| token_contract == contract);
+            let response = match from_binary(msg).unwrap() {
+                Cw721BaseQueryMsg::Tokens {
+                    start_after, limit, ..
+                } => to_binary(&TokensResponse {
+                    tokens: tokens
+                        .map(|(_, token_id, _)| token_id.clone())
+                        .filter(|token_id| match &start_after {
+                            Some(start) => token_id > start,
+                            None => true,
+                        })
+                        .take(limit.unwrap_or(10) as usize)
+                        .collect(),
+                }),
+                Cw721BaseQueryMsg::AllNftInfo { token_id, .. } => {
+                    let (_, _, extension) =
+                        tokens.clone().find(|(_, id, _)| *id == token_id).unwrap();
+                    to_binary(&AllNftInfoResponse {
+                        access: OwnerOfResponse {
+                            owner: "sender".to_string(),
+                            approvals: vec![],
+                        },
+                        info: NftInfoResponse {
+                            token_uri: Some(format!("https://{}", token_id)),
+                            extension: extension.clone(),
+                        },
+                    })
+                }
+                _ => panic!("unexpected cw721 query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+    }
+
+    #[test]
+    fn tokens_detailed() {
+        let metadata = |name: String| Metadata {
+            name: Some(name),
+            description: None,
+            external_link: None,
+            royalties: None,
+            init_price: None,
+        };
+        let mut tokens = (0..3)
+            .map(|i| {
+                let token_id = format!("Hope.{}", i);
+                let extension = metadata(format!("hope{}", i));
+                ("nft_contract".to_string(), token_id, extension)
+            })
+            .collect::<Vec<_>>();
+        tokens.push((
+            "pets".to_string(),
+            "Pet.0".to_string(),
+            metadata("pet".to_string()),
+        ));
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: Cw721Querier {
+                base: MockQuerier::new(&[]),
+                tokens: tokens.clone(),
+            },
+            custom_query_type: PhantomData,
+        };
+        setup_contract(deps.as_mut(), Uint128::new(1));
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(3),
+            prices: vec![],
+            per_wallet_limit: Uint128::new(1),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let page =
+            query_token_info(deps.as_ref(), "sender".to_string(), None, None, Some(2)).unwrap();
+        assert_eq!(
+            page.tokens,
+            vec!["Hope.0".to_string(), "Hope.1".to_string()]
+        );
+        let page = query_token_info(
+            deps.as_ref(),
+            "sender".to_string(),
+            None,
+            Some("Hope.1".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(page.tokens, vec!["Hope.2".to_string()]);
+
+        // registered collections are paged from their own nft contract
+        let page = query_token_info(
+            deps.as_ref(),
+            "sender".to_string(),
+            Some("pets".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(page.tokens, vec!["Pet.0".to_string()]);
+        let detailed = query_tokens_detailed(
+            deps.as_ref(),
+            "sender".to_string(),
+            Some("pets".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(detailed.tokens[0].extension, tokens[3].2);
+        let err = query_token_info(
+            deps.as_ref(),
+            "sender".to_string(),
+            Some("cats".to_string()),
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::UnknownCollection {}.to_string())
+        );
+
+        let detailed = query_tokens_detailed(
+            deps.as_ref(),
+            "sender".to_string(),
+            None,
+            Some("Hope.0".to_string()),
+            Some(5),
+        )
+        .unwrap();
+        assert_eq!(
+            detailed.tokens,
+            vec![
+                TokenDetail {
+                    token_id: "Hope.1".to_string(),
+                    token_uri: Some("https://Hope.1".to_string()),
+                    extension: tokens[1].2.clone(),
+                },
+                TokenDetail {
+                    token_id: "Hope.2".to_string(),
+                    token_uri: Some("https://Hope.2".to_string()),
+                    extension: tokens[2].2.clone(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn user_info() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let user_info = query_user_info(deps.as_ref(), "stranger".to_string()).unwrap();
+        assert_eq!(user_info, None);
+        let msg = QueryMsg::GetUserInfo {
+            address: "stranger".to_string(),
+        };
+        let res: Option<UserInfo> =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res, None);
+
+        // addresses are validated instead of aborting the query
+        for address in ["", "xy"] {
+            assert!(query_user_info(deps.as_ref(), address.to_string()).is_err());
+            assert!(query_token_count(deps.as_ref(), address.to_string(), None).is_err());
+            assert!(query_get_balance(deps.as_ref(), address.to_string()).is_err());
+            assert!(
+                query_token_info(deps.as_ref(), address.to_string(), None, None, None).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn queries_without_config() {
+        let deps = mock_dependencies();
+        let address = || "sender".to_string();
+        let msgs = vec![
+            QueryMsg::GetTokenAddress {},
+            QueryMsg::GetBalance { address: address() },
+            QueryMsg::GetContractInfo {},
+            QueryMsg::GetTokenInfo {
+                address: address(),
+                collection: None,
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetTokensDetailed {
+                address: address(),
+                collection: None,
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetTokenCount {
+                address: address(),
+                collection: None,
+            },
+            QueryMsg::GetNftAddress {},
+            QueryMsg::GetAllUsers {
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetUsersInfo {
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetUserCount {},
+            QueryMsg::GetUserInfo { address: address() },
+            QueryMsg::GetTopMinters { limit: None },
+            QueryMsg::GetMaximumNft {},
+            QueryMsg::GetMaxPerTx {},
+            QueryMsg::GetMintPrice {
+                asset: token_asset("token_contract"),
+            },
+            QueryMsg::GetPaymentTokens {
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetNativePrices {},
+            QueryMsg::GetStateInfo { collection: None },
+            QueryMsg::GetConfig {},
+            QueryMsg::GetMintStatus {
+                collection: None,
+                address: Some(address()),
+                asset: None,
+            },
+            QueryMsg::GetCollections {
+                start_after: None,
+                limit: None,
+            },
+            QueryMsg::GetPhases {},
+            QueryMsg::GetActivePhase {},
+            QueryMsg::GetOwnership {},
+            QueryMsg::GetRoles {},
+            QueryMsg::GetExchange {},
+            QueryMsg::GetTreasury {},
+            QueryMsg::SimulateBuyToken {
+                amount: Uint128::new(1),
+                address: Some(address()),
+            },
+            QueryMsg::SimulateMint {
+                sender: address(),
+                amount: Uint128::new(1),
+                msg: simple_mint_msg(),
+                token: None,
+            },
+            QueryMsg::GetMintRecord {
+                token_id: "Hope.0".to_string(),
+            },
+            QueryMsg::GetMintHistory {
+                minter: Some(address()),
+                start_time: None,
+                end_time: None,
+                start_after: None,
+                limit: None,
+            },
+        ];
+
+        // every query answers with a result or an error, none of them panics
+        let failing: Vec<String> = msgs
+            .into_iter()
+            .filter(|msg| query(deps.as_ref(), mock_env(), msg.clone()).is_err())
+            .map(|msg| format!("{:?}", msg).split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            failing,
+            vec![
+                "GetTokenAddress",
+                "GetBalance",
+                "GetContractInfo",
+                "GetTokenInfo",
+                "GetTokensDetailed",
+                "GetTokenCount",
+                "GetNftAddress",
+                "GetMaximumNft",
+                "GetMintPrice",
+                "GetStateInfo",
+                "GetOwnership",
+                "GetExchange",
+                "SimulateBuyToken",
+                "SimulateMint",
+            ]
+        );
+    }
+
+    #[test]
+    fn mint_status() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(10));
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetNativePrice {
+            denom: "ujuno".to_string(),
+            price: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let status = query_mint_status(deps.as_ref(), mock_env(), None, None, None).unwrap();
+        assert_eq!(
+            status.config,
+            ConfigResponse {
+                owner: Some("creator".to_string()),
+                token_address: Some("token_contract".to_string()),
+                nft_address: Some("nft_contract".to_string()),
+                payout_address: None,
+                max_supply: Some(Uint128::new(2000)),
+                maximum_nft: Some(Uint128::new(5)),
+                max_per_tx: None,
+            }
+        );
+        assert_eq!(status.remaining, Uint128::new(2000));
+        assert_eq!(status.wallet, None);
+
+        // an allowlist phase with its own price and wallet limit
+        let now = mock_env().block.time;
+        let msg = ExecuteMsg::SetPhases {
+            phases: vec![Phase {
+                name: "presale".to_string(),
+                start: now,
+                end: None,
+                prices: vec![Asset {
+                    info: token_asset("token_contract"),
+                    amount: Uint128::new(5),
+                }],
+                per_wallet_limit: Some(Uint128::new(3)),
+                eligibility: Eligibility::Allowlist {
+                    addresses: vec!["sender".to_string()],
+                },
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(5),
+            msg: to_binary(&simple_mint_msg()).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("token_contract", &[]),
+            msg,
+        )
+        .unwrap();
+
+        let status = query_mint_status(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some("sender".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(status.phase.unwrap().name, "presale");
+        assert_eq!(
+            status.prices,
+            vec![
+                Asset {
+                    info: token_asset("token_contract"),
+                    amount: Uint128::new(5),
+                },
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "ujuno".to_string(),
+                    },
+                    amount: Uint128::new(100),
+                },
+            ]
+        );
+        assert_eq!(status.minted, Uint128::new(1));
+        assert_eq!(
+            status.wallet,
+            Some(WalletStatus {
+                address: "sender".to_string(),
+                asset: Some(token_asset("token_contract")),
+                minted: Uint128::new(1),
+                mintable: Uint128::new(2),
+                reason: None,
+            })
+        );
+
+        // the wallet status follows the asset asked for
+        let native = AssetInfo::NativeToken {
+            denom: "ujuno".to_string(),
+        };
+        let status = query_mint_status(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some("sender".to_string()),
+            Some(native.clone()),
+        )
+        .unwrap();
+        let wallet = status.wallet.unwrap();
+        assert_eq!(wallet.asset, Some(native));
+        assert_eq!(wallet.mintable, Uint128::new(2));
+        let status = query_mint_status(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some("sender".to_string()),
+            Some(token_asset("other_token")),
+        )
+        .unwrap();
+        let wallet = status.wallet.unwrap();
+        assert_eq!(wallet.asset, None);
+        assert_eq!(wallet.mintable, Uint128::zero());
+        assert_eq!(
+            wallet.reason,
+            Some(ContractError::InvalidToken {}.to_string())
+        );
+
+        let status = query_mint_status(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some("stranger".to_string()),
+            None,
+        )
+        .unwrap();
+        let wallet = status.wallet.unwrap();
+        assert_eq!(wallet.mintable, Uint128::zero());
+        assert_eq!(
+            wallet.reason,
+            Some(ContractError::NotEligible {}.to_string())
+        );
+
+        let msg = ExecuteMsg::Pause { reason: None };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let status = query_mint_status(
+            deps.as_ref(),
+            mock_env(),
+            None,
+            Some("sender".to_string()),
+            None,
+        )
+        .unwrap();
+        assert!(status.paused);
+        assert_eq!(
+            status.wallet.unwrap().reason,
+            Some(ContractError::Paused {}.to_string())
+        );
+        let msg = ExecuteMsg::Unpause {};
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // registered collections report their own supply, prices and wallet counts
+        let msg = ExecuteMsg::SetCollection {
+            nft_address: "pets".to_string(),
+            token_prefix: "Pet".to_string(),
+            max_supply: Uint128::new(1),
+            prices: vec![Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "ujuno".to_string(),
+                },
+                amount: Uint128::new(70),
+            }],
+            per_wallet_limit: Uint128::new(1),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let pets_status = |deps: Deps| {
+            query_mint_status(
+                deps,
+                mock_env(),
+                Some("pets".to_string()),
+                Some("sender".to_string()),
+                None,
+            )
+            .unwrap()
+        };
+        let status = pets_status(deps.as_ref());
+        assert_eq!(status.prices[0].amount, Uint128::new(5));
+        assert_eq!(status.prices[1].amount, Uint128::new(70));
+        assert_eq!(status.remaining, Uint128::new(1));
+        assert_eq!(
+            status.wallet,
+            Some(WalletStatus {
+                address: "sender".to_string(),
+                asset: Some(token_asset("token_contract")),
+                minted: Uint128::zero(),
+                mintable: Uint128::new(1),
+                reason: None,
+            })
+        );
+
+        let mut mint_msg = simple_mint_msg();
+        mint_msg.nft_addr = Some("pets".to_string());
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(5),
+            msg: to_binary(&mint_msg).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("token_contract", &[]),
+            msg,
+        )
+        .unwrap();
+        let status = pets_status(deps.as_ref());
+        assert_eq!(status.minted, Uint128::new(1));
+        assert_eq!(status.remaining, Uint128::zero());
+        let wallet = status.wallet.unwrap();
+        assert_eq!(wallet.minted, Uint128::new(1));
+        assert_eq!(wallet.reason, Some(ContractError::MintEnded {}.to_string()));
+    }
+
+    #[test]
+    fn simulate_mint() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(100));
+        let simulate = |deps: Deps, amount: u128, quantity: u32| {
+            let mut msg = simple_mint_msg();
+            msg.quantity = Some(quantity);
+            query_simulate_mint(
+                deps,
+                mock_env(),
+                "sender".to_string(),
+                Uint128::new(amount),
+                msg,
+                None,
+            )
+        };
+
+        let res = simulate(deps.as_ref(), 250, 2).unwrap();
+        assert_eq!(
+            res,
+            SimulateMintResponse {
+                token_ids: vec!["Hope.0".to_string(), "Hope.1".to_string()],
+                mint_price: Uint128::new(100),
+                payout: Uint128::new(200),
+                payout_address: "creator".to_string(),
+                refund: Uint128::new(50),
+            }
+        );
+        // nothing was written
+        assert_eq!(
+            query_get_info(deps.as_ref(), None).unwrap().total_nft,
+            Uint128::zero()
+        );
+        assert_eq!(
+            query_token_count(deps.as_ref(), "sender".to_string(), None)
+                .unwrap()
+                .owned_nft_number,
+            Uint128::zero()
+        );
+
+        // the simulation matches the mint that follows
+        let info = mock_info("token_contract", &[]);
+        let mut msg = simple_mint_msg();
+        msg.quantity = Some(2);
+        let message = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "sender".to_string(),
+            amount: Uint128::new(250),
+            msg: to_binary(&msg).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), info, message).unwrap();
+        assert_eq!(
+            query_user_info(deps.as_ref(), "sender".to_string())
+                .unwrap()
+                .unwrap()
+                .nft,
+            res.token_ids
+        );
+        let res = simulate(deps.as_ref(), 100, 1).unwrap();
+        assert_eq!(res.token_ids, vec!["Hope.2".to_string()]);
+
+        // failures carry the error the mint would hit
+        let err = simulate(deps.as_ref(), 99, 1).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::Notenough {}.to_string())
+        );
+        let err = simulate(deps.as_ref(), 400, 4).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::MintExceeded {}.to_string())
+        );
+        let err = query_simulate_mint(
+            deps.as_ref(),
+            mock_env(),
+            "sender".to_string(),
+            Uint128::new(100),
+            simple_mint_msg(),
+            Some("forged_token".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::InvalidToken {}.to_string())
+        );
+    }
+
+    #[test]
+    fn renounce_revokes_roles() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), Uint128::new(1));
+
+        let creator = mock_info("creator", &[]);
+        let admin = mock_info("admin", &[]);
+        let msg = ExecuteMsg::GrantRole {
+            address: "admin".to_string(),
+            role: Role::Admin,
+        };
+        execute(deps.as_mut(), mock_env(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetMaximumNft {
+            amount: Uint128::new(10),
+        };
+        execute(deps.as_mut(), mock_env(), admin.clone(), msg.clone()).unwrap();
+
+        let renounce = ExecuteMsg::RenounceOwnership {};
+        execute(deps.as_mut(), mock_env(), creator, renounce).unwrap();
+        assert_eq!(query_roles(deps.as_ref()).unwrap(), vec![]);
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::GrantRole {
+            address: "other".to_string(),
+            role: Role::Admin,
+        };
+        let err = execute(deps.as_mut(), mock_env(), admin, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn merkle_leaf_encoding() {
+        // sha256 of `sender` and `other,2`
+        assert_eq!(
+            hex::encode(leaf_hash("sender", None)),
+            "0a367b92cf0b037dfd89960ee832d56f7fc151681bb41e53690e776f5786998a"
+        );
+        assert_eq!(
+            hex::encode(leaf_hash("other", Some("2"))),
+            "f0f33d62a9dbd1b65239d0099cc9882f0cf886793d4058b325190035eb24f019"
+        );
+        assert_ne!(
+            leaf_hash("juno1x", Some("5")),
+            leaf_hash("juno1", Some("x5"))
+        );
+        assert_ne!(
+            leaf_hash("juno1x", Some("15")),
+            leaf_hash("juno1x1", Some("5"))
+        );
     }
 }
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp};
+use cw_utils::ParseReplyError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,30 +7,93 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Cannot migrate from contract {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous_version} down to {current_version}")]
+    CannotDowngrade {
+        previous_version: String,
+        current_version: String,
+    },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReply { id: u64 },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Less than zero")]
-    ZeorError {},
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Cw20 token is not accepted as payment")]
+    InvalidToken {},
+
+    #[error("Send exactly one coin of an accepted denom")]
+    InvalidFunds {},
 
-    
     #[error("Not enough funds")]
-    Notenough{},
+    Notenough {},
+
+    #[error("Minting is paused")]
+    Paused {},
 
-    
     #[error("Mint is ended")]
-    MintEnded{},
+    MintEnded {},
 
     #[error("You can not mint anymore")]
-    MintExceeded{},
+    MintExceeded {},
 
+    #[error("Invalid config: {reason}")]
+    InvalidConfig { reason: String },
 
-    #[error("Escrow expired (end_height {end_height:?} end_time {end_time:?})")]
-    Expired {
-        end_height: Option<u64>,
-        end_time: Option<u64>,
-    },
+    #[error("Collection is not registered")]
+    UnknownCollection {},
+
+    #[error("Max supply can only be lowered and not below the minted supply")]
+    InvalidSupply {},
+
+    #[error("Token issuance cap reached")]
+    IssuanceCapExceeded {},
+
+    #[error("Purchase cap for this address reached")]
+    PurchaseCapExceeded {},
+
+    #[error("Redemption limit for this period reached")]
+    RedemptionLimitExceeded {},
+
+    #[error("Not enough reserves in the treasury")]
+    InsufficientReserves {},
+
+    #[error("Spread can not exceed 100%")]
+    InvalidSpread {},
+
+    #[error("Quantity must be at least one")]
+    InvalidQuantity {},
+
+    #[error("Too many nfts in a single transaction")]
+    MaxPerTxExceeded {},
+
+    #[error("Sale phase not started (start {start})")]
+    PhaseNotStarted { start: Timestamp },
+
+    #[error("Sale ended (end {end})")]
+    PhaseEnded { end: Timestamp },
+
+    #[error("Sale phases must be named uniquely, ordered and not overlap")]
+    InvalidPhases {},
+
+    #[error("Not eligible to mint in the current phase")]
+    NotEligible {},
+
+    #[error("Invalid merkle root")]
+    InvalidMerkleRoot {},
 
-    #[error("Escrow not expired")]
-    NotExpired {},
+    #[error("Invalid merkle proof")]
+    InvalidProof {},
 }
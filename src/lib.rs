@@ -1,4 +1,6 @@
 pub mod contract;
 mod error;
+pub mod merkle;
+mod migrations;
 pub mod msg;
 pub mod state;
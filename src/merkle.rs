@@ -0,0 +1,30 @@
+use sha2::Digest;
+
+pub type Hash = [u8; 32];
+
+// leaves commit to the address and, when given, its allocation as `address,allocation`
+pub fn leaf_hash(address: &str, allocation: Option<&str>) -> Hash {
+    let leaf = match allocation {
+        Some(allocation) => format!("{},{}", address, allocation),
+        None => address.to_string(),
+    };
+    sha2::Sha256::digest(leaf.as_bytes()).into()
+}
+
+// pairs are sorted before hashing so proofs don't need to carry a direction
+pub fn hash_pair(a: &Hash, b: &Hash) -> Hash {
+    let mut pair = [*a, *b];
+    pair.sort_unstable();
+    sha2::Sha256::digest(&pair.concat()).into()
+}
+
+pub fn verify_proof(leaf: Hash, proof: &[Hash], root: &Hash) -> bool {
+    let computed = proof.iter().fold(leaf, |hash, p| hash_pair(&hash, p));
+    &computed == root
+}
+
+pub fn decode_hash(hash: &str) -> Option<Hash> {
+    let mut buf: Hash = [0; 32];
+    hex::decode_to_slice(hash, &mut buf).ok()?;
+    Some(buf)
+}
@@ -0,0 +1,132 @@
+use cosmwasm_std::{Order, StdResult, Storage, Uint128};
+use cosmwasm_storage::{singleton, singleton_read, to_length_prefixed};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    mint_ledger, store_token_prefix, users, Collection, MintRecord, State, UserInfo, COLLECTIONS,
+    UNIQUE_MINTERS,
+};
+
+// Keys of the cosmwasm_storage singletons used up to 0.10. The cw-storage-plus
+// items kept the same names, only the length prefix is dropped.
+const LEGACY_SINGLETONS: &[&str] = &[
+    "config",
+    "token_address",
+    "nft_address",
+    "NFTMaximum",
+    "MaxPerTx",
+    "Phases",
+    "PendingOwner",
+    "PayoutAddress",
+    "Exchange",
+    "TotalIssued",
+    "Redemption",
+    "RedemptionWindow",
+];
+
+// State as stored by deployments from before ownership, supply and pause
+// settings were added
+#[derive(Serialize, Deserialize)]
+struct LegacyState {
+    total_nft: Uint128,
+    owner: String,
+}
+
+// Deployments without a cw2 version may still hold the original state layout.
+// Their receive hook stopped minting at 2000 nfts, which becomes the supply cap.
+pub fn migrate_legacy_state(storage: &mut dyn Storage) -> StdResult<()> {
+    if singleton_read::<State>(storage, b"config").load().is_ok() {
+        return Ok(());
+    }
+    let legacy: LegacyState = singleton_read(storage, b"config").load()?;
+    singleton(storage, b"config").save(&State {
+        total_nft: legacy.total_nft,
+        owner: Some(legacy.owner),
+        max_supply: Uint128::new(2000),
+        paused: false,
+        pause_reason: None,
+    })
+}
+
+// 0.11 moved all state to cw-storage-plus. The old buckets already share the
+// key layout of the new maps, so only the singletons are moved and the
+// secondary indexes are built for the existing entries. The mint ledger
+// indexes are left to migrate_mint_ledger_indexes.
+pub fn migrate_storage_layout(storage: &mut dyn Storage) -> StdResult<()> {
+    for key in LEGACY_SINGLETONS {
+        let legacy_key = to_length_prefixed(key.as_bytes());
+        if let Some(value) = storage.get(&legacy_key) {
+            storage.set(key.as_bytes(), &value);
+            storage.remove(&legacy_key);
+        }
+    }
+
+    let users = users();
+    let entries: Vec<(String, UserInfo)> = users
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (address, user) in entries {
+        users.save(storage, &address, &user)?;
+    }
+    Ok(())
+}
+
+// 0.12 keeps the number of unique minters next to the user list
+pub fn migrate_unique_minters(storage: &mut dyn Storage) -> StdResult<()> {
+    let count = users()
+        .keys_raw(storage, None, None, Order::Ascending)
+        .count();
+    UNIQUE_MINTERS.save(storage, &(count as u64))
+}
+
+// 0.13 indexes the mint ledger by time, re-saving every record builds all of
+// its indexes. The minter index is replaced by the minter and time index.
+pub fn migrate_mint_ledger_indexes(storage: &mut dyn Storage) -> StdResult<()> {
+    let minter_index = to_length_prefixed(b"MintLedger__minter");
+    let mut minter_index_end = minter_index.clone();
+    if let Some(last) = minter_index_end.last_mut() {
+        *last += 1;
+    }
+    let stale: Vec<Vec<u8>> = storage
+        .range(
+            Some(&minter_index),
+            Some(&minter_index_end),
+            Order::Ascending,
+        )
+        .map(|(key, _)| key)
+        .collect();
+    for key in stale {
+        storage.remove(&key);
+    }
+
+    let ledger = mint_ledger();
+    let records: Vec<(String, MintRecord)> = ledger
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (token_id, record) in records {
+        ledger.save(storage, &token_id, &record)?;
+    }
+    Ok(())
+}
+
+// 0.13 reserves token prefixes for good, starting with the registered collections
+pub fn migrate_token_prefixes(storage: &mut dyn Storage) -> StdResult<()> {
+    let collections: Vec<Collection> = COLLECTIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, collection)| collection))
+        .collect::<StdResult<_>>()?;
+    for collection in collections {
+        store_token_prefix(storage, &collection.token_prefix, &collection.nft_address)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) fn store_legacy_state(storage: &mut dyn Storage, total_nft: Uint128, owner: &str) {
+    singleton(storage, b"config")
+        .save(&LegacyState {
+            total_nft,
+            owner: owner.to_string(),
+        })
+        .unwrap();
+}
@@ -1,35 +1,270 @@
-use crate::state::Royalty;
-use cosmwasm_std::{ Uint128};
-use cw20::Cw20ReceiveMsg;
+use crate::state::{
+    Asset, AssetInfo, ExchangeConfig, Metadata, PaymentToken, PendingOwner, Phase,
+    RedemptionConfig, Role, Royalty,
+};
+use cosmwasm_std::{Coin, Timestamp, Uint128};
+use cw20::{Cw20Coin, Cw20ReceiveMsg};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    // hope cw20 token minted by BuyToken
+    pub token: TokenContract,
+    // cw721 contract the nfts are minted on
+    pub nft: NftContract,
+    // number of nfts a single wallet can mint
+    pub maximum_nft: Uint128,
+    // cw20 tokens accepted as payment and the price of a single nft in each
+    pub payment_tokens: Vec<PaymentToken>,
+    // native coins accepted as payment, the amount is the price of a single nft
+    pub native_prices: Vec<Coin>,
+    // total number of nfts that can ever be minted
+    pub max_supply: Uint128,
+    // receives mint proceeds, defaults to the owner
+    pub payout_address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    // price of the cw20 token of deployments from before versioning, required
+    // when that token isn't a payment token yet
+    pub legacy_mint_price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenContract {
+    // an already deployed cw20 this contract is allowed to mint
+    Existing {
+        address: String,
+    },
+    // instantiates a cw20-base contract with this contract as minter
+    Instantiate {
+        code_id: u64,
+        label: String,
+        name: String,
+        symbol: String,
+        decimals: u8,
+        initial_balances: Vec<Cw20Coin>,
+        cap: Option<Uint128>,
+        // accepts the new token as payment at this price of a single nft
+        mint_price: Option<Uint128>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NftContract {
+    // an already deployed cw721 this contract is allowed to mint
+    Existing {
+        address: String,
+    },
+    // instantiates a cw721-base contract with this contract as minter
+    Instantiate {
+        code_id: u64,
+        label: String,
+        name: String,
+        symbol: String,
+    },
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
-    SetTokenAddress { address: String },
-    SetNftAddress { address: String },
-    SetMaximumNft {amount: Uint128},
-    BuyToken { amount:i32 }
+    SetTokenAddress {
+        address: String,
+    },
+    SetNftAddress {
+        address: String,
+    },
+    SetMaximumNft {
+        amount: Uint128,
+    },
+    SetMaxPerTx {
+        amount: Uint128,
+    },
+    SetMaxSupply {
+        amount: Uint128,
+    },
+    SetPhases {
+        phases: Vec<Phase>,
+    },
+    SetMerkleRoot {
+        phase: String,
+        root: String,
+    },
+    Pause {
+        reason: Option<String>,
+    },
+    Unpause {},
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    RenounceOwnership {},
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+        role: Role,
+    },
+    Airdrop {
+        recipients: Vec<String>,
+        msg: HopeMintMsg,
+    },
+    Withdraw {
+        asset: Asset,
+        recipient: Option<String>,
+    },
+    AddPaymentToken {
+        address: String,
+        price: Uint128,
+    },
+    RemovePaymentToken {
+        address: String,
+    },
+    Mint(HopeMintMsg),
+    SetNativePrice {
+        denom: String,
+        price: Uint128,
+    },
+    RemoveNativePrice {
+        denom: String,
+    },
+    BuyToken {
+        amount: Uint128,
+    },
+    SetExchange {
+        exchange: ExchangeConfig,
+    },
+    SetRedemption {
+        redemption: RedemptionConfig,
+    },
+    SetCollection {
+        nft_address: String,
+        token_prefix: String,
+        max_supply: Uint128,
+        prices: Vec<Asset>,
+        per_wallet_limit: Uint128,
+    },
+    RemoveCollection {
+        nft_address: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum Cw20HookMsg {
+    Mint(HopeMintMsg),
+    // sell hope tokens back to the treasury
+    Redeem {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum QueryMsg {
     GetTokenAddress {},
-    GetBalance { address: String },
+    GetBalance {
+        address: String,
+    },
     GetContractInfo {},
-    GetTokenInfo { address: String },
-    GetTokenCount{address:String},
+    GetTokenInfo {
+        address: String,
+        collection: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTokensDetailed {
+        address: String,
+        collection: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTokenCount {
+        address: String,
+        collection: Option<String>,
+    },
     GetNftAddress {},
-    GetAllUsers {},
-    GetUserInfo { address: String },
-    GetMaximumNft{},
-    GetStateInfo{}
+    GetAllUsers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetUsersInfo {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetUserCount {},
+    GetUserInfo {
+        address: String,
+    },
+    // users by the number of nfts they minted, most first
+    GetTopMinters {
+        limit: Option<u32>,
+    },
+    GetMaximumNft {},
+    GetMaxPerTx {},
+    GetMintPrice {
+        asset: AssetInfo,
+    },
+    GetPaymentTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetNativePrices {},
+    GetStateInfo {
+        collection: Option<String>,
+    },
+    GetConfig {},
+    // merkle phases report the proof error for a wallet, no proof is given here.
+    // The wallet status is for paying with asset, by default the first accepted one.
+    GetMintStatus {
+        collection: Option<String>,
+        address: Option<String>,
+        asset: Option<AssetInfo>,
+    },
+    GetCollections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetMintRecord {
+        token_id: String,
+    },
+    // confirmed mints in the order they were confirmed, start_time is inclusive and
+    // end_time exclusive, mints confirmed before 0.13 count as time zero
+    GetMintHistory {
+        minter: Option<String>,
+        start_time: Option<Timestamp>,
+        end_time: Option<Timestamp>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetPhases {},
+    GetActivePhase {},
+    GetOwnership {},
+    GetRoles {},
+    GetExchange {},
+    GetTreasury {},
+    SimulateBuyToken {
+        amount: Uint128,
+        address: Option<String>,
+    },
+    // dry run of a cw20 Send carrying a mint hook, fails with the error the mint would hit
+    SimulateMint {
+        sender: String,
+        amount: Uint128,
+        msg: HopeMintMsg,
+        // payment token the Send goes through, defaults to the hope token
+        token: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -49,4 +284,107 @@ pub struct HopeMintMsg {
     pub init_price: Option<Uint128>,
     // nft address of specified collection
     pub nft_addr: Option<String>,
+    // number of nfts minted with this metadata, defaults to one
+    pub quantity: Option<u32>,
+    // hex encoded merkle proof for allowlisted phases
+    pub proof: Option<Vec<String>>,
+    // allocation committed to in the merkle leaf, if any
+    pub allocation: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateResponse {
+    pub total_nft: Uint128,
+    pub owner: Option<String>,
+    pub max_supply: Uint128,
+    pub remaining_supply: Uint128,
+    pub paused: bool,
+    pub pause_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<PendingOwner>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleInfo {
+    pub address: String,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateBuyTokenResponse {
+    pub amount: Uint128,
+    pub cost: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateMintResponse {
+    pub token_ids: Vec<String>,
+    // price of a single nft once collection and phase prices are applied
+    pub mint_price: Uint128,
+    // sent to the payout address
+    pub payout: Uint128,
+    pub payout_address: String,
+    // sent back to the sender
+    pub refund: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryResponse {
+    pub reserves: Vec<Coin>,
+    pub redemption: Option<RedemptionConfig>,
+    pub redeemed_in_period: Uint128,
+    pub remaining_in_period: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenDetail {
+    pub token_id: String,
+    pub token_uri: Option<String>,
+    pub extension: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokensDetailedResponse {
+    pub tokens: Vec<TokenDetail>,
+}
+
+// mint configuration, unset values are left empty
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Option<String>,
+    pub token_address: Option<String>,
+    pub nft_address: Option<String>,
+    pub payout_address: Option<String>,
+    pub max_supply: Option<Uint128>,
+    pub maximum_nft: Option<Uint128>,
+    pub max_per_tx: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintStatusResponse {
+    pub config: ConfigResponse,
+    pub phase: Option<Phase>,
+    // price of a single nft in every accepted asset, phase prices applied
+    pub prices: Vec<Asset>,
+    pub minted: Uint128,
+    pub remaining: Uint128,
+    pub paused: bool,
+    pub pause_reason: Option<String>,
+    pub wallet: Option<WalletStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WalletStatus {
+    pub address: String,
+    // payment asset the status is for, empty when no asset is accepted
+    pub asset: Option<AssetInfo>,
+    pub minted: Uint128,
+    // nfts the address can still mint right now
+    pub mintable: Uint128,
+    // error a mint would fail with when nothing can be minted
+    pub reason: Option<String>,
 }
@@ -1,45 +1,182 @@
-use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128,Decimal};
-use cosmwasm_storage::{
-    bucket, bucket_read, singleton, singleton_read, ReadonlySingleton, Singleton,
-};
-use cw_storage_plus::Map;
+use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-
-static CONFIG_KEY: &[u8] = b"config";
-pub const CONFIG_ADDRESS: &[u8] = b"token_address";
-pub const CONFIG_NFT_ADDRESS: &[u8] = b"nft_address";
-pub const CONFIG_USERS: &[u8] = b"User";
-pub const CONFIG_USER_INFO: &[u8] = b"UserInfo";
-pub const CONFIG_COUNT: &[u8] = b"TokenCount";
-pub const CONFIG_MAXIMUM: &[u8]=b"NFTMaximum";
+use std::fmt;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    pub total_nft:Uint128,
-    pub owner:String
+    pub total_nft: Uint128,
+    // none once ownership has been renounced
+    pub owner: Option<String>,
+    pub max_supply: Uint128,
+    pub paused: bool,
+    pub pause_reason: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: String },
+    NativeToken { denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Eligibility {
+    Everyone {},
+    Allowlist { addresses: Vec<String> },
+    // hex encoded sha256 merkle root of the allowed addresses
+    Merkle { root: String },
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Royalty{
+pub struct Phase {
+    pub name: String,
+    pub start: Timestamp,
+    // the last phase may stay open until the supply runs out
+    pub end: Option<Timestamp>,
+    // overrides the registry price for the listed payment assets
+    pub prices: Vec<Asset>,
+    // nfts a single wallet may mint during this phase
+    pub per_wallet_limit: Option<Uint128>,
+    pub eligibility: Eligibility,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentToken {
     pub address: String,
-    pub royalty_rate:Decimal
+    pub price: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TokenCount{
-    pub owned_nft_number:Uint128,
-    pub total_nft:Uint128
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    // changes the mint configuration
+    Admin,
+    // pauses minting and runs airdrops
+    Operator,
+    // withdraws funds held by the contract
+    Treasurer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Admin => write!(f, "admin"),
+            Role::Operator => write!(f, "operator"),
+            Role::Treasurer => write!(f, "treasurer"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct UserInfo {
-    pub address :String,
-    pub nft: Vec<String>
+pub struct PendingOwner {
+    pub address: String,
+    pub expiry: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeConfig {
+    pub denom: String,
+    // native units paid for a single cw20 unit
+    pub price: Decimal,
+    // cw20 that can ever be bought from the contract
+    pub issuance_cap: Uint128,
+    // cw20 a single address can ever buy
+    pub per_address_cap: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionConfig {
+    pub denom: String,
+    // native units paid back for a single cw20 unit, before the spread
+    pub price: Decimal,
+    // share of the buy-back price kept by the treasury
+    pub spread: Decimal,
+    // burn redeemed tokens instead of keeping them in the contract
+    pub burn: bool,
+    // length of a redemption period in seconds
+    pub period: u64,
+    // cw20 that can be redeemed across all users within one period
+    pub period_limit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionWindow {
+    pub start: Timestamp,
+    pub redeemed: Uint128,
+}
+
+// cw721 contract minted through this contract next to the default collection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Collection {
+    pub nft_address: Addr,
+    // token ids are minted as `{token_prefix}.{n}`
+    pub token_prefix: String,
+    pub max_supply: Uint128,
+    pub total_nft: Uint128,
+    // overrides the registry price for the listed payment assets
+    pub prices: Vec<Asset>,
+    // nfts a single wallet can mint in this collection
+    pub per_wallet_limit: Uint128,
+}
+
+impl Collection {
+    pub fn token_id(&self, number: Uint128) -> String {
+        [self.token_prefix.clone(), number.to_string()].join(".")
+    }
+}
+
+// cw721 mint sent out and waiting for its reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingMint {
+    pub token_id: String,
+    pub minter: Addr,
+    pub payment: Option<Asset>,
+    pub phase: Option<String>,
+}
+
+// mint confirmed by the cw721 contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintRecord {
+    pub token_id: String,
+    pub minter: Addr,
+    pub height: u64,
+    // the fields below are empty for mints confirmed before 0.13
+    pub time: Option<Timestamp>,
+    // price paid for this token, empty for airdrops
+    pub payment: Option<Asset>,
+    // sale phase the token was minted in
+    pub phase: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub address: String,
+    pub royalty_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenCount {
+    pub owned_nft_number: Uint128,
+    pub total_nft: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserInfo {
+    pub address: String,
+    pub nft: Vec<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Metadata{
+pub struct Metadata {
     // Identifies the asset to which this NFT represents
     pub name: Option<String>,
     // Describes the asset to which this NFT represents (may be empty)
@@ -52,63 +189,532 @@ pub struct Metadata{
     pub init_price: Option<Uint128>,
 }
 
+pub const CONFIG: Item<State> = Item::new("config");
+pub const TOKEN_ADDRESS: Item<Addr> = Item::new("token_address");
+pub const NFT_ADDRESS: Item<Addr> = Item::new("nft_address");
+pub const MAXIMUM_NFT: Item<Uint128> = Item::new("NFTMaximum");
+pub const MAX_PER_TX: Item<Uint128> = Item::new("MaxPerTx");
+pub const PHASES: Item<Vec<Phase>> = Item::new("Phases");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("PendingOwner");
+pub const PAYOUT_ADDRESS: Item<Addr> = Item::new("PayoutAddress");
+pub const EXCHANGE: Item<ExchangeConfig> = Item::new("Exchange");
+pub const TOTAL_ISSUED: Item<Uint128> = Item::new("TotalIssued");
+pub const REDEMPTION: Item<RedemptionConfig> = Item::new("Redemption");
+pub const REDEMPTION_WINDOW: Item<RedemptionWindow> = Item::new("RedemptionWindow");
+// mint price of the hope token instantiated in factory mode, until its reply
+pub const PENDING_TOKEN_PRICE: Item<Uint128> = Item::new("PendingTokenPrice");
+
+pub const TOKEN_COUNTS: Map<&Addr, Uint128> = Map::new("TokenCount");
+pub const NATIVE_PRICES: Map<&str, Uint128> = Map::new("NativePrice");
+pub const PAYMENT_TOKENS: Map<&Addr, Uint128> = Map::new("PaymentToken");
+pub const PHASE_MINTS: Map<(&str, &Addr), Uint128> = Map::new("PhaseMints");
+pub const ROLES: Map<&Addr, Vec<Role>> = Map::new("Roles");
+pub const ISSUED: Map<&Addr, Uint128> = Map::new("Issued");
+pub const PENDING_MINTS: Map<u64, PendingMint> = Map::new("PendingMints");
+pub const MINT_SEQUENCE: Item<u64> = Item::new("MintSequence");
+pub const UNIQUE_MINTERS: Item<u64> = Item::new("UniqueMinters");
+pub const COLLECTIONS: Map<&Addr, Collection> = Map::new("Collections");
+pub const COLLECTION_MINTS: Map<(&Addr, &Addr), Uint128> = Map::new("CollectionMints");
+// token counters of removed collections, so a re-registered collection never reuses a token id
+pub const COLLECTION_COUNTERS: Map<&Addr, Uint128> = Map::new("CollectionCounters");
+// collection that owns each token prefix, kept on removal so no other collection reissues its ids
+pub const TOKEN_PREFIXES: Map<&str, Addr> = Map::new("TokenPrefixes");
 
-pub const USERS: Map<&str, UserInfo> = Map::new("User");
+pub struct UserIndexes<'a> {
+    // users by the number of nfts they minted
+    pub mint_count: MultiIndex<'a, u64, UserInfo, String>,
+}
+
+impl<'a> IndexList<UserInfo> for UserIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<UserInfo>> + '_> {
+        let v: Vec<&dyn Index<UserInfo>> = vec![&self.mint_count];
+        Box::new(v.into_iter())
+    }
+}
 
-pub fn config(storage: &mut dyn Storage) -> Singleton<State> {
-    singleton(storage, CONFIG_KEY)
+pub fn users<'a>() -> IndexedMap<'a, &'a str, UserInfo, UserIndexes<'a>> {
+    let indexes = UserIndexes {
+        mint_count: MultiIndex::new(
+            |user: &UserInfo| user.nft.len() as u64,
+            "User",
+            "User__mint_count",
+        ),
+    };
+    IndexedMap::new("User", indexes)
 }
 
-pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<State> {
-    singleton_read(storage, CONFIG_KEY)
+pub struct MintIndexes<'a> {
+    // confirmed mints by the time they were confirmed in nanoseconds
+    pub time: MultiIndex<'a, u64, MintRecord, String>,
+    // confirmed mints of every minter by time
+    pub minter_time: MultiIndex<'a, (Addr, u64), MintRecord, String>,
 }
 
-pub fn store_maximum_nft(storage: &mut dyn Storage,maximum:&Uint128) -> StdResult<()> {
-    Singleton::new(storage, CONFIG_MAXIMUM).save(maximum)
+impl<'a> IndexList<MintRecord> for MintIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MintRecord>> + '_> {
+        let v: Vec<&dyn Index<MintRecord>> = vec![&self.time, &self.minter_time];
+        Box::new(v.into_iter())
+    }
+}
+
+// records confirmed before the time was kept are indexed at time zero
+fn mint_time(record: &MintRecord) -> u64 {
+    record.time.map_or(0, |time| time.nanos())
+}
+
+pub fn mint_ledger<'a>() -> IndexedMap<'a, &'a str, MintRecord, MintIndexes<'a>> {
+    let indexes = MintIndexes {
+        time: MultiIndex::new(mint_time, "MintLedger", "MintLedger__time"),
+        minter_time: MultiIndex::new(
+            |record: &MintRecord| (record.minter.clone(), mint_time(record)),
+            "MintLedger",
+            "MintLedger__minter_time",
+        ),
+    };
+    IndexedMap::new("MintLedger", indexes)
+}
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn store_maximum_nft(storage: &mut dyn Storage, maximum: &Uint128) -> StdResult<()> {
+    MAXIMUM_NFT.save(storage, maximum)
 }
 
 pub fn read_maximum_nft(storage: &dyn Storage) -> StdResult<Uint128> {
-    ReadonlySingleton::new(storage, CONFIG_MAXIMUM).load()
+    MAXIMUM_NFT.load(storage)
+}
+
+pub fn store_max_per_tx(storage: &mut dyn Storage, maximum: &Uint128) -> StdResult<()> {
+    MAX_PER_TX.save(storage, maximum)
+}
+
+pub fn read_max_per_tx(storage: &dyn Storage) -> Option<Uint128> {
+    MAX_PER_TX.may_load(storage).ok().flatten()
+}
+
+pub fn store_phases(storage: &mut dyn Storage, phases: &[Phase]) -> StdResult<()> {
+    PHASES.save(storage, &phases.to_vec())
+}
+
+pub fn read_phases(storage: &dyn Storage) -> StdResult<Vec<Phase>> {
+    let phases = PHASES.may_load(storage)?;
+    Ok(phases.unwrap_or_default())
+}
+
+pub fn store_phase_mints(
+    storage: &mut dyn Storage,
+    phase: &str,
+    user: &Addr,
+    count: &Uint128,
+) -> StdResult<()> {
+    PHASE_MINTS.save(storage, (phase, user), count)
+}
+
+pub fn read_phase_mints(storage: &dyn Storage, phase: &str, user: &Addr) -> Option<Uint128> {
+    PHASE_MINTS.may_load(storage, (phase, user)).ok().flatten()
+}
+
+pub fn store_collection(storage: &mut dyn Storage, collection: &Collection) -> StdResult<()> {
+    COLLECTIONS.save(storage, &collection.nft_address, collection)
+}
+
+pub fn remove_collection(storage: &mut dyn Storage, nft_address: &Addr) -> StdResult<()> {
+    if let Some(collection) = read_collection(storage, nft_address) {
+        COLLECTION_COUNTERS.save(storage, nft_address, &collection.total_nft)?;
+        COLLECTIONS.remove(storage, nft_address);
+    }
+    Ok(())
+}
+
+pub fn read_collection(storage: &dyn Storage, nft_address: &Addr) -> Option<Collection> {
+    COLLECTIONS.may_load(storage, nft_address).ok().flatten()
+}
+
+// number of tokens ever minted into a collection, registered or removed
+pub fn read_collection_counter(storage: &dyn Storage, nft_address: &Addr) -> Uint128 {
+    match read_collection(storage, nft_address) {
+        Some(collection) => collection.total_nft,
+        None => COLLECTION_COUNTERS
+            .may_load(storage, nft_address)
+            .ok()
+            .flatten()
+            .unwrap_or_default(),
+    }
+}
+
+pub fn store_token_prefix(
+    storage: &mut dyn Storage,
+    token_prefix: &str,
+    nft_address: &Addr,
+) -> StdResult<()> {
+    TOKEN_PREFIXES.save(storage, token_prefix, nft_address)
+}
+
+pub fn read_token_prefix(storage: &dyn Storage, token_prefix: &str) -> StdResult<Option<Addr>> {
+    TOKEN_PREFIXES.may_load(storage, token_prefix)
+}
+
+pub fn read_collections(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Collection>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    COLLECTIONS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, collection)| collection))
+        .collect()
+}
+
+pub fn store_collection_mints(
+    storage: &mut dyn Storage,
+    collection: &Addr,
+    user: &Addr,
+    count: &Uint128,
+) -> StdResult<()> {
+    COLLECTION_MINTS.save(storage, (collection, user), count)
+}
+
+pub fn read_collection_mints(storage: &dyn Storage, collection: &Addr, user: &Addr) -> Uint128 {
+    COLLECTION_MINTS
+        .may_load(storage, (collection, user))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+// hands out unique ids for the mint submessages
+pub fn next_mint_sequence(storage: &mut dyn Storage) -> StdResult<u64> {
+    let sequence = MINT_SEQUENCE.may_load(storage)?.unwrap_or_default();
+    MINT_SEQUENCE.save(storage, &(sequence + 1))?;
+    Ok(sequence)
+}
+
+pub fn store_pending_mint(
+    storage: &mut dyn Storage,
+    reply_id: u64,
+    pending: &PendingMint,
+) -> StdResult<()> {
+    PENDING_MINTS.save(storage, reply_id, pending)
+}
+
+pub fn take_pending_mint(storage: &mut dyn Storage, reply_id: u64) -> Option<PendingMint> {
+    let pending = PENDING_MINTS.may_load(storage, reply_id).ok().flatten()?;
+    PENDING_MINTS.remove(storage, reply_id);
+    Some(pending)
+}
+
+pub fn store_mint_record(storage: &mut dyn Storage, record: &MintRecord) -> StdResult<()> {
+    mint_ledger().save(storage, &record.token_id, record)
+}
+
+pub fn read_mint_record(storage: &dyn Storage, token_id: &str) -> Option<MintRecord> {
+    mint_ledger().may_load(storage, token_id).ok().flatten()
+}
+
+// mint records in the order they were confirmed, optionally of a single minter
+// and within [start_time, end_time)
+pub fn read_mint_history(
+    storage: &dyn Storage,
+    minter: Option<Addr>,
+    start_time: Option<Timestamp>,
+    end_time: Option<Timestamp>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MintRecord>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let ledger = mint_ledger();
+
+    // the page continues after the last record returned, if that is past start_time
+    let start_nanos = start_time.map_or(0, |time| time.nanos());
+    let mut min = Bound::inclusive((start_nanos, String::new()));
+    if let Some(token_id) = start_after {
+        let record = ledger.load(storage, &token_id)?;
+        if mint_time(&record) >= start_nanos {
+            min = Bound::exclusive((mint_time(&record), token_id));
+        }
+    }
+    let max = end_time.map(|time| Bound::exclusive((time.nanos(), String::new())));
+
+    let records = match minter {
+        Some(minter) => ledger.idx.minter_time.sub_prefix(minter).range(
+            storage,
+            Some(min),
+            max,
+            Order::Ascending,
+        ),
+        None => ledger
+            .idx
+            .time
+            .range(storage, Some(min), max, Order::Ascending),
+    };
+    records
+        .take(limit)
+        .map(|item| item.map(|(_, record)| record))
+        .collect()
+}
+
+pub fn store_pending_owner(storage: &mut dyn Storage, pending: &PendingOwner) -> StdResult<()> {
+    PENDING_OWNER.save(storage, pending)
+}
+
+pub fn remove_pending_owner(storage: &mut dyn Storage) {
+    PENDING_OWNER.remove(storage)
+}
+
+pub fn read_pending_owner(storage: &dyn Storage) -> StdResult<Option<PendingOwner>> {
+    PENDING_OWNER.may_load(storage)
+}
+
+pub fn store_payout_address(storage: &mut dyn Storage, payout: &Addr) -> StdResult<()> {
+    PAYOUT_ADDRESS.save(storage, payout)
+}
+
+pub fn read_payout_address(storage: &dyn Storage) -> StdResult<Option<Addr>> {
+    PAYOUT_ADDRESS.may_load(storage)
+}
+
+pub fn store_pending_token_price(storage: &mut dyn Storage, price: &Uint128) -> StdResult<()> {
+    PENDING_TOKEN_PRICE.save(storage, price)
+}
+
+pub fn take_pending_token_price(storage: &mut dyn Storage) -> Option<Uint128> {
+    let price = PENDING_TOKEN_PRICE.may_load(storage).ok().flatten()?;
+    PENDING_TOKEN_PRICE.remove(storage);
+    Some(price)
+}
+
+pub fn store_exchange(storage: &mut dyn Storage, exchange: &ExchangeConfig) -> StdResult<()> {
+    EXCHANGE.save(storage, exchange)
+}
+
+pub fn read_exchange(storage: &dyn Storage) -> StdResult<ExchangeConfig> {
+    EXCHANGE.load(storage)
+}
+
+pub fn store_total_issued(storage: &mut dyn Storage, issued: &Uint128) -> StdResult<()> {
+    TOTAL_ISSUED.save(storage, issued)
+}
+
+pub fn read_total_issued(storage: &dyn Storage) -> Uint128 {
+    TOTAL_ISSUED
+        .may_load(storage)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+pub fn store_redemption(storage: &mut dyn Storage, redemption: &RedemptionConfig) -> StdResult<()> {
+    REDEMPTION.save(storage, redemption)
+}
+
+pub fn read_redemption(storage: &dyn Storage) -> StdResult<RedemptionConfig> {
+    REDEMPTION.load(storage)
+}
+
+pub fn store_redemption_window(
+    storage: &mut dyn Storage,
+    window: &RedemptionWindow,
+) -> StdResult<()> {
+    REDEMPTION_WINDOW.save(storage, window)
+}
+
+pub fn read_redemption_window(storage: &dyn Storage) -> Option<RedemptionWindow> {
+    REDEMPTION_WINDOW.may_load(storage).ok().flatten()
 }
 
 pub fn store_token_address(storage: &mut dyn Storage, token_address: &Addr) -> StdResult<()> {
-    Singleton::new(storage, CONFIG_ADDRESS).save(token_address)
+    TOKEN_ADDRESS.save(storage, token_address)
 }
 
 pub fn read_token_address(storage: &dyn Storage) -> StdResult<Addr> {
-    ReadonlySingleton::new(storage, CONFIG_ADDRESS).load()
+    TOKEN_ADDRESS.load(storage)
 }
 
 pub fn store_nft_address(storage: &mut dyn Storage, nft_address: &Addr) -> StdResult<()> {
-    Singleton::new(storage, CONFIG_NFT_ADDRESS).save(nft_address)
+    NFT_ADDRESS.save(storage, nft_address)
 }
 
 pub fn read_nft_address(storage: &dyn Storage) -> StdResult<Addr> {
-    ReadonlySingleton::new(storage, CONFIG_NFT_ADDRESS).load()
+    NFT_ADDRESS.load(storage)
 }
 
 pub fn store_users(storage: &mut dyn Storage, user: &Addr, user_info: UserInfo) -> StdResult<()> {
-    bucket(storage, CONFIG_USERS).save(user.as_bytes(), &user_info)
+    let users = users();
+    if users.may_load(storage, user.as_str())?.is_none() {
+        let count = read_unique_minters(storage);
+        UNIQUE_MINTERS.save(storage, &(count + 1))?;
+    }
+    users.save(storage, user.as_str(), &user_info)
+}
+
+pub fn read_unique_minters(storage: &dyn Storage) -> u64 {
+    UNIQUE_MINTERS
+        .may_load(storage)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
 }
 
 pub fn read_user_info(storage: &dyn Storage, user: &Addr) -> Option<UserInfo> {
-    match bucket_read(storage, CONFIG_USERS).load(user.as_bytes()) {
-        Ok(v) => Some(v),
-        _ => None,
-    }
+    users().may_load(storage, user.as_str()).ok().flatten()
 }
 
-pub fn read_users(storage: &dyn Storage) -> StdResult<Vec<String>> {
-    USERS.keys(storage, None, None, Order::Ascending).collect()
+// users sorted by the number of nfts they minted, most first
+pub fn read_top_minters(storage: &dyn Storage, limit: Option<u32>) -> StdResult<Vec<UserInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    users()
+        .idx
+        .mint_count
+        .range(storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, user)| user))
+        .collect()
 }
 
-pub fn store_token_count(storage: &mut dyn Storage, user: &Addr, token_count: Uint128) -> StdResult<()> {
-    bucket(storage, CONFIG_COUNT).save(user.as_bytes(), &token_count)
+pub fn read_users(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    users()
+        .keys(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+pub fn read_users_info(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<UserInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    users()
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, user)| user))
+        .collect()
+}
+
+pub fn store_token_count(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    token_count: Uint128,
+) -> StdResult<()> {
+    TOKEN_COUNTS.save(storage, user, &token_count)
 }
 
 pub fn read_token_count(storage: &dyn Storage, user: &Addr) -> Option<Uint128> {
-    match bucket_read(storage, CONFIG_COUNT).load(user.as_bytes()) {
-        Ok(v) => Some(v),
-        _ => None,
+    TOKEN_COUNTS.may_load(storage, user).ok().flatten()
+}
+
+pub fn store_native_price(
+    storage: &mut dyn Storage,
+    denom: &str,
+    price: &Uint128,
+) -> StdResult<()> {
+    NATIVE_PRICES.save(storage, denom, price)
+}
+
+pub fn remove_native_price(storage: &mut dyn Storage, denom: &str) {
+    NATIVE_PRICES.remove(storage, denom)
+}
+
+pub fn read_native_price(storage: &dyn Storage, denom: &str) -> Option<Uint128> {
+    NATIVE_PRICES.may_load(storage, denom).ok().flatten()
+}
+
+pub fn read_native_prices(storage: &dyn Storage) -> StdResult<Vec<(String, Uint128)>> {
+    NATIVE_PRICES
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn store_payment_token(
+    storage: &mut dyn Storage,
+    token: &Addr,
+    price: &Uint128,
+) -> StdResult<()> {
+    PAYMENT_TOKENS.save(storage, token, price)
+}
+
+pub fn remove_payment_token(storage: &mut dyn Storage, token: &Addr) {
+    PAYMENT_TOKENS.remove(storage, token)
+}
+
+pub fn read_payment_token(storage: &dyn Storage, token: &Addr) -> Option<Uint128> {
+    PAYMENT_TOKENS.may_load(storage, token).ok().flatten()
+}
+
+pub fn read_all_payment_tokens(storage: &dyn Storage) -> StdResult<Vec<(Addr, Uint128)>> {
+    PAYMENT_TOKENS
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn read_payment_tokens(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PaymentToken>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    PAYMENT_TOKENS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, price)| PaymentToken {
+                address: address.to_string(),
+                price,
+            })
+        })
+        .collect()
+}
+
+pub fn store_roles(storage: &mut dyn Storage, user: &Addr, roles: &[Role]) -> StdResult<()> {
+    if roles.is_empty() {
+        ROLES.remove(storage, user);
+        return Ok(());
+    }
+    ROLES.save(storage, user, &roles.to_vec())
+}
+
+pub fn read_roles(storage: &dyn Storage, user: &Addr) -> Vec<Role> {
+    ROLES
+        .may_load(storage, user)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+pub fn clear_roles(storage: &mut dyn Storage) -> StdResult<()> {
+    let holders: Vec<Addr> = ROLES
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for holder in holders {
+        ROLES.remove(storage, &holder);
     }
-}
\ No newline at end of file
+    Ok(())
+}
+
+pub fn read_all_roles(storage: &dyn Storage) -> StdResult<Vec<(Addr, Vec<Role>)>> {
+    ROLES.range(storage, None, None, Order::Ascending).collect()
+}
+
+pub fn store_issued(storage: &mut dyn Storage, user: &Addr, issued: &Uint128) -> StdResult<()> {
+    ISSUED.save(storage, user, issued)
+}
+
+pub fn read_issued(storage: &dyn Storage, user: &Addr) -> Uint128 {
+    ISSUED
+        .may_load(storage, user)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}